@@ -0,0 +1,85 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use jdcp::{
+    decode_jdcp,
+    message::{
+        parse_age_response, parse_cast_spell_response, parse_class_response, parse_combat_response,
+        parse_conditions_response, parse_data, parse_hp_response, parse_level_response,
+        parse_race_response, parse_rest_response, parse_roll_response, parse_skills_response,
+        parse_spell_slots_response, parse_spells_response, parse_stats_response, parse_xp_response,
+        MessageData, MessageType,
+    },
+};
+use nom::{branch::alt, error::VerboseError, IResult};
+
+/// A response per info type, from the first parser `alt` used to try to the last.
+const RESPONSES: [(&str, &str); 6] = [
+    ("stats", "RESPONSE Bart STATS 16 12 14 10 10 8"),
+    (
+        "spell slots",
+        "RESPONSE Bart SPELLSLOTS 1:1/4 2:0/2 pact:3:1/2",
+    ),
+    ("conditions", "RESPONSE Bart CONDITIONS POISONED|PRONE 1"),
+    ("xp", "RESPONSE Bart XP 6500"),
+    (
+        "roll result",
+        "RESPONSE Bart ROLLRESULT 4d6kh3 => 13 +0 d6:3 d6:1x d6:6 d6:4",
+    ),
+    ("hp", "RESPONSE Bart HP 34/41"),
+];
+
+/// How responses were parsed before dispatching on the info type, kept as the baseline.
+fn parse_data_with_alt(i: &[u8]) -> IResult<&[u8], MessageData, VerboseError<&[u8]>> {
+    alt((
+        parse_stats_response,
+        parse_age_response,
+        parse_class_response,
+        parse_race_response,
+        parse_level_response,
+        parse_spell_slots_response,
+        parse_spells_response,
+        parse_cast_spell_response,
+        parse_rest_response,
+        parse_conditions_response,
+        parse_skills_response,
+        parse_combat_response,
+        parse_xp_response,
+        parse_roll_response,
+        parse_hp_response,
+    ))(i)
+}
+
+fn response_data(c: &mut Criterion) {
+    let mut group = c.benchmark_group("response data");
+    for (name, text) in RESPONSES {
+        let bytes = text
+            .parse::<jdcp::message::Message>()
+            .unwrap()
+            .encode_jdcp();
+        // Everything after the name, where the two approaches differ.
+        let data = &bytes[bytes.iter().position(|b| *b == 0).unwrap() + 1..];
+        group.bench_with_input(BenchmarkId::new("dispatch", name), data, |b, data| {
+            b.iter(|| parse_data(black_box(data), &MessageType::RESPONSE))
+        });
+        group.bench_with_input(BenchmarkId::new("alt", name), data, |b, data| {
+            b.iter(|| parse_data_with_alt(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_jdcp");
+    for (name, text) in RESPONSES {
+        let bytes = text
+            .parse::<jdcp::message::Message>()
+            .unwrap()
+            .encode_jdcp();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &bytes, |b, bytes| {
+            b.iter(|| decode_jdcp(black_box(bytes)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, response_data, decode);
+criterion_main!(benches);
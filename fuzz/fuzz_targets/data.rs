@@ -0,0 +1,11 @@
+#![no_main]
+
+use jdcp::message::{parse_data, MessageType};
+use libfuzzer_sys::fuzz_target;
+
+// Everything after the header, for each message type.
+fuzz_target!(|bytes: &[u8]| {
+    for message_type in MessageType::ALL {
+        let _ = parse_data(bytes, &message_type);
+    }
+});
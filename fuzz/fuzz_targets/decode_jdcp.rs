@@ -0,0 +1,9 @@
+#![no_main]
+
+use jdcp::message::DecodeMode;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = jdcp::decode_jdcp(bytes);
+    let _ = jdcp::decode_jdcp_with(bytes, DecodeMode::LENIENT);
+});
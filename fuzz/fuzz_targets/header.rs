@@ -0,0 +1,13 @@
+#![no_main]
+
+use jdcp::message::{
+    data_size::parse_data_size, parse_character_name, parse_info_type, parse_message_type,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = parse_message_type(bytes);
+    let _ = parse_character_name(bytes);
+    let _ = parse_info_type(bytes);
+    let _ = parse_data_size(bytes);
+});
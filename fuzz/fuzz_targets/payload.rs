@@ -0,0 +1,29 @@
+#![no_main]
+
+use jdcp::message::character_data::*;
+use libfuzzer_sys::fuzz_target;
+
+// The first two bytes pick the payload size for the parsers that take one.
+fuzz_target!(|bytes: &[u8]| {
+    let Some((size, payload)) = bytes.split_first_chunk::<2>() else {
+        return;
+    };
+    let size = u16::from_le_bytes(*size);
+    let _ = parse_stats(payload);
+    let _ = parse_age(payload);
+    let _ = parse_class(payload);
+    let _ = parse_race(payload);
+    let _ = parse_level(payload);
+    let _ = parse_hp(payload);
+    let _ = parse_spell_slots(payload);
+    let _ = parse_spells(payload, size);
+    let _ = parse_cast_spell(payload);
+    let _ = parse_rest(payload);
+    let _ = parse_conditions(payload);
+    let _ = parse_skills(payload);
+    let _ = parse_combat(payload);
+    let _ = parse_xp(payload);
+    let _ = parse_roll(payload, size);
+    let _ = parse_roll_result(payload, size);
+    let _ = parse_roll_log(payload, size);
+});
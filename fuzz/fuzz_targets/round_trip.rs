@@ -0,0 +1,12 @@
+#![no_main]
+
+use jdcp::decode_jdcp;
+use libfuzzer_sys::fuzz_target;
+
+// Whatever decodes must encode to bytes that decode to the same message.
+fuzz_target!(|bytes: &[u8]| {
+    if let Ok((_, message)) = decode_jdcp(bytes) {
+        let encoded = message.encode_jdcp();
+        assert_eq!(decode_jdcp(&encoded), Ok((&b""[..], message)));
+    }
+});
@@ -0,0 +1,268 @@
+//! proptest `Arbitrary` impls for the protocol types, behind the `proptest` feature. They only
+//! generate values a peer could actually send, e.g. hit points no higher than their max, rolls
+//! made by the real dice roller and messages whose `data_size` matches their data, so anything
+//! generated encodes and decodes back unchanged:
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn handles_any_message(message in any::<jdcp::message::Message<'static>>()) {
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use proptest::{array::uniform6, collection::vec, prelude::*, sample::select};
+
+use crate::{
+    character::{
+        character_data::{
+            class_type::ClassType,
+            combat_stats::CombatStats,
+            conditions::{ConditionFlags, Conditions},
+            health_points::HealthPoints,
+            race_kind::RaceKind,
+            rest_kind::RestKind,
+            roll_record::{RollLogEntry, RollRecord},
+            skill_proficiencies::{SkillFlags, SkillProficiencies},
+            spell_slots::{PactSlots, SpellSlots},
+            stat_block::StatBlock,
+            CharacterData,
+        },
+        experience::MAX_LEVEL,
+    },
+    dice::{roll, SeededRng},
+    message::{InfoType, Message, MessageType},
+};
+
+const DICE: [u32; 7] = [4, 6, 8, 10, 12, 20, 100];
+
+fn name() -> impl Strategy<Value = String> {
+    "[A-Za-z]{1,16}"
+}
+
+impl Arbitrary for InfoType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        select(InfoType::ALL.to_vec()).boxed()
+    }
+}
+
+/// Built in classes, and `Unknown` only for bytes none of them use.
+impl Arbitrary for ClassType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<u8>()
+            .prop_map(|id| ClassType::from(&[id][..]))
+            .boxed()
+    }
+}
+
+/// Built in races, and `Unknown` only for bytes none of them use.
+impl Arbitrary for RaceKind {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<u8>().prop_map(|id| RaceKind::from(&[id][..])).boxed()
+    }
+}
+
+/// Scores from 1 to 30.
+impl Arbitrary for StatBlock {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        uniform6(1..=30u8)
+            .prop_map(|scores| StatBlock::from(&scores[..]))
+            .boxed()
+    }
+}
+
+/// Current hit points never above the max.
+impl Arbitrary for HealthPoints {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<u8>()
+            .prop_flat_map(|max| (0..=max, Just(max)))
+            .prop_map(|(current, max)| HealthPoints { current, max })
+            .boxed()
+    }
+}
+
+fn conditions() -> impl Strategy<Value = Conditions> {
+    (any::<u16>(), 0..=Conditions::MAX_EXHAUSTION).prop_map(|(flags, exhaustion)| {
+        Conditions::new(ConditionFlags::from_bits_truncate(flags), exhaustion)
+    })
+}
+
+/// Expertise only in skills the character is proficient in.
+fn skills() -> impl Strategy<Value = SkillProficiencies> {
+    (any::<u32>(), any::<u32>()).prop_map(|(proficient, expertise)| {
+        let proficient = SkillFlags::from_bits_truncate(proficient);
+        SkillProficiencies {
+            proficient,
+            expertise: SkillFlags::from_bits_truncate(expertise) & proficient,
+        }
+    })
+}
+
+/// A max and how many of them are used.
+fn slots() -> impl Strategy<Value = (u8, u8)> {
+    (0..=4u8).prop_flat_map(|max| (Just(max), 0..=max))
+}
+
+fn spell_slots() -> impl Strategy<Value = SpellSlots> {
+    (vec(slots(), 9), 0..=5u8, slots()).prop_map(|(levels, pact_level, (pact_max, pact_used))| {
+        let mut spell_slots = SpellSlots::default();
+        for (level, (max, used)) in levels.into_iter().enumerate() {
+            spell_slots.max[level] = max;
+            spell_slots.used[level] = used;
+        }
+        spell_slots.pact = PactSlots {
+            level: pact_level,
+            max: pact_max,
+            used: pact_used,
+        };
+        spell_slots
+    })
+}
+
+/// Dice expressions the roller accepts, e.g. `4d6kh3-1`.
+fn expression() -> impl Strategy<Value = String> {
+    (1..=10u8, select(DICE.to_vec()))
+        .prop_flat_map(|(count, sides)| {
+            (
+                Just(count),
+                Just(sides),
+                prop_oneof![
+                    Just(String::new()),
+                    (1..=count).prop_map(|keep| format!("kh{}", keep)),
+                    (1..=count).prop_map(|keep| format!("kl{}", keep)),
+                ],
+                -5..=5i32,
+            )
+        })
+        .prop_map(|(count, sides, keep, modifier)| match modifier {
+            0 => format!("{}d{}{}", count, sides, keep),
+            _ => format!("{}d{}{}{:+}", count, sides, keep, modifier),
+        })
+}
+
+/// An expression rolled with the real roller, so every die and the total agree.
+fn roll_record() -> impl Strategy<Value = RollRecord> {
+    (expression(), any::<u64>()).prop_map(|(expression, seed)| {
+        let result =
+            roll(&expression, &mut SeededRng::new(seed)).expect("generated expressions are valid");
+        RollRecord { expression, result }
+    })
+}
+
+impl Arbitrary for CharacterData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            any::<StatBlock>().prop_map(CharacterData::STATS),
+            any::<u16>().prop_map(CharacterData::AGE),
+            any::<ClassType>().prop_map(CharacterData::CLASS),
+            any::<RaceKind>().prop_map(CharacterData::RACE),
+            (1..=MAX_LEVEL).prop_map(CharacterData::LEVEL),
+            any::<HealthPoints>().prop_map(CharacterData::HP),
+            spell_slots().prop_map(CharacterData::SPELLSLOTS),
+            vec("[A-Za-z][A-Za-z' ]{0,23}", 0..5).prop_map(CharacterData::SPELLS),
+            (1..=9u8).prop_map(CharacterData::CASTSPELL),
+            select(vec![RestKind::SHORT, RestKind::LONG]).prop_map(CharacterData::REST),
+            conditions().prop_map(CharacterData::CONDITIONS),
+            conditions().prop_map(CharacterData::ADDCONDITIONS),
+            conditions().prop_map(CharacterData::REMOVECONDITIONS),
+            skills().prop_map(CharacterData::SKILLS),
+            any::<(u8, i8, u8)>().prop_map(|(armor_class, initiative, speed)| {
+                CharacterData::COMBAT(CombatStats {
+                    armor_class,
+                    initiative,
+                    speed,
+                })
+            }),
+            any::<u32>().prop_map(CharacterData::XP),
+            any::<u32>().prop_map(CharacterData::AWARDXP),
+            expression().prop_map(CharacterData::ROLL),
+            roll_record().prop_map(CharacterData::ROLLRESULT),
+            vec(
+                (name(), roll_record())
+                    .prop_map(|(roller, record)| RollLogEntry { roller, record }),
+                0..4
+            )
+            .prop_map(CharacterData::ROLLLOG),
+        ]
+        .boxed()
+    }
+}
+
+/// Requests and subscriptions without data, responses and writes with data of their info type.
+impl Arbitrary for Message<'static> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            (
+                select(vec![MessageType::REQUEST, MessageType::SUBSCRIBE]),
+                name(),
+                any::<InfoType>(),
+            )
+                .prop_map(|(message_type, name, info_type)| Message {
+                    message_type,
+                    character_name: name.into(),
+                    info_type,
+                    data_size: 0,
+                    data: None,
+                }),
+            (
+                select(vec![MessageType::RESPONSE, MessageType::WRITE]),
+                name(),
+                any::<CharacterData>(),
+            )
+                .prop_filter_map(
+                    "data too large for one message",
+                    |(message_type, name, data)| {
+                        Some(Message {
+                            message_type,
+                            character_name: name.into(),
+                            info_type: data.info_type(),
+                            data_size: data.size()?,
+                            data: Some(data),
+                        })
+                    }
+                ),
+        ]
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_arbitrary_tests {
+    use super::*;
+    use crate::decode_jdcp;
+
+    proptest! {
+        #[test]
+        fn generated_messages_survive_the_wire(message in any::<Message<'static>>()) {
+            let encoded = message.encode_jdcp();
+            prop_assert_eq!(decode_jdcp(&encoded), Ok((&b""[..], message)));
+        }
+
+        #[test]
+        fn generated_messages_survive_the_text_form(message in any::<Message<'static>>()) {
+            prop_assert_eq!(message.to_string().parse::<Message>().ok(), Some(message));
+        }
+    }
+}
@@ -1,61 +1,107 @@
 use std::{
     io::{stdin, Read, Write},
     net::TcpStream,
-    str::from_utf8,
+    thread,
 };
 
 use jdcp::{
-    character::character_data::{health_points::HealthPoints, CharacterData},
+    character::character_data::{
+        conditions::{ConditionFlags, Conditions},
+        health_points::HealthPoints,
+        CharacterData,
+    },
+    framing::FrameSplitter,
     message::{info_type::InfoType, Message, MessageType},
 };
 
+fn print_replies(mut stream: TcpStream) {
+    let mut data = [0_u8; 50];
+    let mut splitter = FrameSplitter::default();
+    loop {
+        match stream.read(&mut data) {
+            Ok(0) => break,
+            Ok(size) => {
+                for frame in splitter.push(&data[..size]) {
+                    match frame.message {
+                        Some(message) => println!("Reply: {}", message),
+                        None => println!(
+                            "Skipped {} bytes to the next reply: {:?}",
+                            frame.bytes.len(),
+                            frame.reason
+                        ),
+                    }
+                }
+            }
+            Err(e) => {
+                println!("Failed to receive data: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// A message for Bart, subscribing to conditions when there's no data.
+fn bart(message_type: MessageType, data: Option<CharacterData>) -> Message<'static> {
+    Message {
+        message_type,
+        character_name: "Bart".into(),
+        info_type: data
+            .as_ref()
+            .map_or(InfoType::CONDITIONS, CharacterData::info_type),
+        data_size: data.as_ref().and_then(CharacterData::size).unwrap_or(0),
+        data,
+    }
+}
+
 fn main() {
     println!("Please enter the server port number");
     let port = "12345";
     match TcpStream::connect(format!("127.0.0.1:{}", port)) {
         Ok(mut stream) => {
             println!("Successfully connected!");
+            let replies = stream.try_clone().expect("Failed to clone stream");
+            thread::spawn(move || print_replies(replies));
 
             loop {
                 let mut msg = String::new();
-                println!("Enter '1 ' to send jdcp message");
+                println!("Enter '1' to write HP, '2' to subscribe to conditions, '3' to poison, '4' to cure, '5' to attack or a message such as 'REQUEST Bart HP'");
                 stdin()
                     .read_line(&mut msg)
                     .expect("Failed to read message to write");
-                if msg.trim().eq("1") {
-                    let jdcp_msg = Message {
-                        message_type: MessageType::RESPONSE,
-                        character_name: "Bart",
-                        info_type: InfoType::HP,
-                        data_size: 2,
-                        data: Some(CharacterData::HP(HealthPoints {
+                let poisoned = Conditions::new(ConditionFlags::POISONED, 0);
+                let jdcp_msg = match msg.trim() {
+                    "1" => bart(
+                        MessageType::WRITE,
+                        Some(CharacterData::HP(HealthPoints {
                             current: 34,
                             max: 42,
                         })),
-                    };
-
-                    let jdcp_vec: Vec<u8> = jdcp_msg.encode_jdcp();
-                    stream.write(&jdcp_vec).unwrap();
-                    println!("Awaiting response...  sent bytes: {:?}", jdcp_vec);
-                    println!(
-                        "Awaiting response...  sent message: {:?}",
-                        jdcp::decode_jdcp(&jdcp_vec)
-                    );
-                } else {
-                    println!("No message sent. You entered: {}", msg);
-                }
-                let mut data: [u8; 10] = [0 as u8; 10];
-                match stream.read(&mut data) {
-                    Ok(_) => {
-                        println!(
-                            "Reply: {}",
-                            from_utf8(&data).expect("Error unwrapping response")
-                        );
-                    }
-                    Err(e) => {
-                        println!("Failed to receive data: {}", e);
-                    }
-                }
+                    ),
+                    "2" => bart(MessageType::SUBSCRIBE, None),
+                    "3" => bart(
+                        MessageType::WRITE,
+                        Some(CharacterData::ADDCONDITIONS(poisoned)),
+                    ),
+                    "4" => bart(
+                        MessageType::WRITE,
+                        Some(CharacterData::REMOVECONDITIONS(poisoned)),
+                    ),
+                    "5" => bart(
+                        MessageType::WRITE,
+                        Some(CharacterData::ROLL("1d20+6".to_string())),
+                    ),
+                    text => match text.parse::<Message>() {
+                        Ok(message) => message,
+                        Err(e) => {
+                            println!("No message sent. You entered: {}\n{}", msg, e);
+                            continue;
+                        }
+                    },
+                };
+                let jdcp_vec: Vec<u8> = jdcp_msg.encode_jdcp();
+                stream.write_all(&jdcp_vec).unwrap();
+                println!("Awaiting response...  sent bytes: {:?}", jdcp_vec);
+                println!("Awaiting response...  sent message: {}", jdcp_msg);
             }
         }
         Err(e) => {
@@ -0,0 +1,103 @@
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+
+use jdcp::conformance::{classify, parse_corpus, Outcome};
+
+const USAGE: &str = "Usage: jdcp-conformance check CORPUS [ENDPOINT_ADDRESS]
+       jdcp-conformance serve PORT
+Runs a corpus of test vectors against this crate's codec, or against an endpoint that answers
+each frame with its outcome, and reports pass or fail per vector. serve answers as that endpoint
+using this crate's codec.";
+
+/// How long an endpoint gets to answer each vector.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Sends one vector on its own connection and reads back the endpoint's outcome line.
+fn ask(address: &str, bytes: &[u8]) -> io::Result<String> {
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    stream.write_all(bytes)?;
+    stream.shutdown(Shutdown::Write)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply.trim_end().to_string())
+}
+
+fn check(path: &str, endpoint: Option<&str>) -> Result<bool, String> {
+    let corpus = fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+    let corpus = parse_corpus(&corpus).map_err(|e| format!("{}: {}", path, e))?;
+    let mut failed = 0;
+    for vector in &corpus.vectors {
+        let actual = match endpoint {
+            None => Ok(classify(&vector.bytes).to_string()),
+            Some(address) => ask(address, &vector.bytes).map_err(|e| e.to_string()),
+        };
+        let passed = match &actual {
+            Ok(reply) => reply.parse::<Outcome>().as_ref() == Ok(&vector.expected),
+            Err(_) => false,
+        };
+        if passed {
+            println!("PASS {}", vector.name);
+        } else {
+            failed += 1;
+            println!(
+                "FAIL {}: expected {}, got {}",
+                vector.name,
+                vector.expected,
+                actual.unwrap_or_else(|e| format!("no reply ({})", e))
+            );
+        }
+    }
+    println!(
+        "corpus version {}: {} vectors, {} passed, {} failed",
+        corpus.version,
+        corpus.vectors.len(),
+        corpus.vectors.len() - failed,
+        failed
+    );
+    Ok(failed == 0)
+}
+
+fn answer(mut stream: TcpStream) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    stream.read_to_end(&mut bytes)?;
+    writeln!(stream, "{}", classify(&bytes))
+}
+
+fn serve(port: &str) -> Result<(), String> {
+    let listener = TcpListener::bind(format!("127.0.0.1:{}", port))
+        .map_err(|e| format!("Unable to listen on port {}: {}", port, e))?;
+    println!("Answering conformance vectors on port {}", port);
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || answer(stream).ok());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args[..] {
+        ["check", path] => check(path, None),
+        ["check", path, endpoint] => check(path, Some(endpoint)),
+        ["serve", port] => serve(port).map(|_| true),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
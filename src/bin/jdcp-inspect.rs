@@ -0,0 +1,67 @@
+use std::{
+    env, fs,
+    io::{stdin, Read},
+    process::ExitCode,
+};
+
+use jdcp::inspect::dissect;
+
+const USAGE: &str = "Usage: jdcp-inspect [HEX...] | -f FILE
+Dissects JDCP bytes given as hex arguments, a raw file, or hex or raw bytes on stdin.";
+
+/// Hex digits with any whitespace, `0x` or `\x` between bytes.
+fn parse_hex(text: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    let digits: String = text
+        .replace("\\x", " ")
+        .split_whitespace()
+        .map(|word| word.trim_start_matches("0x"))
+        .collect();
+    hex::decode(digits)
+}
+
+fn read_input(args: &[String]) -> Result<Vec<u8>, String> {
+    match args.first().map(String::as_str) {
+        Some("-f" | "--file") => {
+            let path = args.get(1).ok_or(USAGE)?;
+            fs::read(path).map_err(|e| format!("Unable to read {}: {}", path, e))
+        }
+        Some(_) => parse_hex(&args.join(" ")).map_err(|e| format!("Invalid hex: {}", e)),
+        None => {
+            let mut input = Vec::new();
+            stdin()
+                .read_to_end(&mut input)
+                .map_err(|e| format!("Unable to read stdin: {}", e))?;
+            Ok(std::str::from_utf8(&input)
+                .ok()
+                .and_then(|text| parse_hex(text).ok())
+                .unwrap_or(input))
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if matches!(args.first().map(String::as_str), Some("-h" | "--help")) {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let input = match read_input(&args) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let dissections = dissect(&input);
+    if dissections.is_empty() {
+        println!("No bytes to dissect");
+    }
+    for dissection in &dissections {
+        println!("{}", dissection);
+    }
+    if dissections.iter().any(|d| d.failure.is_some()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,61 @@
+use std::{env, fs, process::ExitCode, time::Duration};
+
+use jdcp::capture::extract_messages;
+
+const USAGE: &str = "Usage: jdcp-pcap CAPTURE PORT
+Decodes the JDCP messages in a pcap or pcapng capture of TCP traffic on PORT.";
+
+fn seconds(since: Duration) -> String {
+    format!("{:>4}.{:06}", since.as_secs(), since.subsec_micros())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (Some(path), Some(Ok(port))) = (args.first(), args.get(1).map(|p| p.parse::<u16>())) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let capture = match fs::read(path) {
+        Ok(capture) => capture,
+        Err(e) => {
+            eprintln!("Unable to read {}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let timeline = match extract_messages(&capture, port) {
+        Ok(timeline) => timeline,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for captured in &timeline.messages {
+        println!(
+            "{}  {} -> {}  {}",
+            seconds(captured.timestamp.saturating_sub(timeline.start)),
+            captured.source,
+            captured.destination,
+            captured.message
+        );
+    }
+    if !timeline.undecodable.is_empty() {
+        println!("\nUndecodable bytes:");
+    }
+    for range in &timeline.undecodable {
+        println!(
+            "{}  {} -> {}  bytes {}..{} ({} bytes, {})",
+            seconds(range.timestamp.saturating_sub(timeline.start)),
+            range.source,
+            range.destination,
+            range.start,
+            range.end,
+            range.end - range.start,
+            match range.category {
+                Some(category) => format!("{}: {:?}", range.reason, category),
+                None => range.reason.to_string(),
+            }
+        );
+    }
+    ExitCode::SUCCESS
+}
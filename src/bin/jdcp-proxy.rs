@@ -0,0 +1,357 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    env,
+    fs::{self, File},
+    io::{self, ErrorKind, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    process::ExitCode,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use jdcp::{
+    framing::FrameSplitter,
+    message::DecodeMode,
+    proxy::{
+        diff, frame_text, parse_recording, Direction, Fault, FaultAction, FaultInjector, Faults,
+        RecordedFrame,
+    },
+};
+
+const USAGE: &str = "Usage: jdcp-proxy record LISTEN_PORT SERVER_ADDRESS SESSION_FILE
+       jdcp-proxy replay SESSION_FILE SERVER_ADDRESS
+       jdcp-proxy faults LISTEN_PORT SERVER_ADDRESS FAULTS
+Records the traffic between clients and a server to a session file, sends a recorded
+session's client frames to a server and compares its replies with the recorded ones, or
+forwards traffic with faults injected. FAULTS is a list of percent chances per frame with
+an optional seed, e.g. split=30,coalesce=10,delay=10,max-delay=250,drop=2,corrupt=2,reset=1,seed=42";
+
+/// How long replay waits, after sending everything, for the server frames it expects.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long replay then waits for any it didn't expect.
+const EXTRA_REPLY_WAIT: Duration = Duration::from_millis(50);
+/// How long coalesced frames wait for another frame before being sent on their own.
+const COALESCE_WAIT: Duration = Duration::from_millis(200);
+
+struct Recorder {
+    started: Instant,
+    file: File,
+}
+
+impl Recorder {
+    fn record(&mut self, connection: usize, direction: Direction, bytes: Vec<u8>) {
+        let frame = RecordedFrame {
+            elapsed: self.started.elapsed(),
+            connection,
+            direction,
+            bytes,
+        };
+        println!("{}", frame);
+        writeln!(self.file, "{}", frame)
+            .unwrap_or_else(|e| println!("Unable to write to the session file: {}", e));
+    }
+}
+
+/// Forwards bytes as they arrive and records each frame once it's complete.
+fn pump(
+    connection: usize,
+    direction: Direction,
+    mut from: TcpStream,
+    mut to: TcpStream,
+    recorder: Arc<Mutex<Recorder>>,
+) {
+    let mut buff = [0_u8; 1024];
+    let mut splitter = FrameSplitter::new(DecodeMode::LENIENT);
+    while let Ok(size @ 1..) = from.read(&mut buff) {
+        if to.write_all(&buff[..size]).is_err() {
+            break;
+        }
+        let frames = splitter.push(&buff[..size]);
+        let mut recorder = recorder.lock().unwrap_or_else(|e| e.into_inner());
+        for frame in frames {
+            recorder.record(connection, direction, frame.bytes);
+        }
+    }
+    if !splitter.pending().is_empty() {
+        let mut recorder = recorder.lock().unwrap_or_else(|e| e.into_inner());
+        recorder.record(connection, direction, splitter.pending().to_vec());
+    }
+    to.shutdown(Shutdown::Write).ok();
+}
+
+fn proxy_connection(
+    connection: usize,
+    client: TcpStream,
+    server_address: &str,
+    recorder: &Arc<Mutex<Recorder>>,
+) -> io::Result<()> {
+    let server = TcpStream::connect(server_address)?;
+    let (upstream, downstream) = (client.try_clone()?, server.try_clone()?);
+    let recorder_up = Arc::clone(recorder);
+    thread::spawn(move || {
+        pump(
+            connection,
+            Direction::CLIENTTOSERVER,
+            upstream,
+            downstream,
+            recorder_up,
+        )
+    });
+    let recorder_down = Arc::clone(recorder);
+    thread::spawn(move || {
+        pump(
+            connection,
+            Direction::SERVERTOCLIENT,
+            server,
+            client,
+            recorder_down,
+        )
+    });
+    Ok(())
+}
+
+fn record(port: &str, server_address: &str, path: &str) -> Result<(), String> {
+    let listener = TcpListener::bind(format!("127.0.0.1:{}", port))
+        .map_err(|e| format!("Unable to listen on port {}: {}", port, e))?;
+    let file = File::create(path).map_err(|e| format!("Unable to create {}: {}", path, e))?;
+    let recorder = Arc::new(Mutex::new(Recorder {
+        started: Instant::now(),
+        file,
+    }));
+    println!(
+        "Proxying port {} to {}, recording to {}",
+        port, server_address, path
+    );
+    for (connection, client) in listener.incoming().enumerate() {
+        match client
+            .and_then(|client| proxy_connection(connection, client, server_address, &recorder))
+        {
+            Ok(()) => println!("Connection {} opened", connection),
+            Err(e) => println!("Connection {} failed: {}", connection, e),
+        }
+    }
+    Ok(())
+}
+
+/// Carries out an injector's actions, returning false once the connection has been reset.
+fn perform(actions: Vec<FaultAction>, from: &TcpStream, to: &mut TcpStream) -> bool {
+    for action in actions {
+        match action {
+            FaultAction::WRITE(bytes) => {
+                if to.write_all(&bytes).is_err() {
+                    return false;
+                }
+            }
+            FaultAction::WAIT(wait) => thread::sleep(wait),
+            FaultAction::RESET => {
+                from.shutdown(Shutdown::Both).ok();
+                to.shutdown(Shutdown::Both).ok();
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Forwards frame by frame, letting the injector split, merge, hold back, drop or damage them.
+fn fault_pump(
+    connection: usize,
+    direction: Direction,
+    mut from: TcpStream,
+    mut to: TcpStream,
+    faults: Faults,
+) {
+    let stream = connection as u64 * 2 + direction as u64;
+    let mut injector = FaultInjector::new(&faults, stream);
+    let mut buff = [0_u8; 1024];
+    let mut splitter = FrameSplitter::new(DecodeMode::LENIENT);
+    let arrow = match direction {
+        Direction::CLIENTTOSERVER => ">",
+        Direction::SERVERTOCLIENT => "<",
+    };
+    loop {
+        from.set_read_timeout(injector.holding().then_some(COALESCE_WAIT))
+            .ok();
+        let actions = match from.read(&mut buff) {
+            Ok(0) => break,
+            Ok(size) => {
+                let mut actions = Vec::new();
+                for frame in splitter.push(&buff[..size]) {
+                    let (mut frame_actions, hit) = injector.apply(&frame.bytes);
+                    let hit: Vec<&str> = hit.iter().map(Fault::name).collect();
+                    println!(
+                        "{} {} {} {}",
+                        connection,
+                        arrow,
+                        if hit.is_empty() {
+                            "-".to_string()
+                        } else {
+                            hit.join(",")
+                        },
+                        frame_text(&frame.bytes)
+                    );
+                    actions.append(&mut frame_actions);
+                }
+                actions
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                injector.flush()
+            }
+            Err(_) => break,
+        };
+        if !perform(actions, &from, &mut to) {
+            println!("{} {} connection reset", connection, arrow);
+            return;
+        }
+    }
+    let mut rest = injector.flush();
+    rest.push(FaultAction::WRITE(splitter.pending().to_vec()));
+    perform(rest, &from, &mut to);
+    to.shutdown(Shutdown::Write).ok();
+}
+
+fn inject_faults(port: &str, server_address: &str, faults: Faults) -> Result<(), String> {
+    let listener = TcpListener::bind(format!("127.0.0.1:{}", port))
+        .map_err(|e| format!("Unable to listen on port {}: {}", port, e))?;
+    println!(
+        "Proxying port {} to {} with faults {}",
+        port, server_address, faults
+    );
+    for (connection, client) in listener.incoming().enumerate() {
+        let opened = client.and_then(|client| {
+            let server = TcpStream::connect(server_address)?;
+            client.set_nodelay(true)?;
+            server.set_nodelay(true)?;
+            let (upstream, downstream) = (client.try_clone()?, server.try_clone()?);
+            let faults_up = faults.clone();
+            thread::spawn(move || {
+                fault_pump(
+                    connection,
+                    Direction::CLIENTTOSERVER,
+                    upstream,
+                    downstream,
+                    faults_up,
+                )
+            });
+            let faults_down = faults.clone();
+            thread::spawn(move || {
+                fault_pump(
+                    connection,
+                    Direction::SERVERTOCLIENT,
+                    server,
+                    client,
+                    faults_down,
+                )
+            });
+            Ok(())
+        });
+        match opened {
+            Ok(()) => println!("Connection {} opened", connection),
+            Err(e) => println!("Connection {} failed: {}", connection, e),
+        }
+    }
+    Ok(())
+}
+
+fn read_replies(connection: usize, mut stream: TcpStream, replies: Sender<(usize, Vec<u8>)>) {
+    let mut buff = [0_u8; 1024];
+    let mut splitter = FrameSplitter::new(DecodeMode::LENIENT);
+    while let Ok(size @ 1..) = stream.read(&mut buff) {
+        for frame in splitter.push(&buff[..size]) {
+            if replies.send((connection, frame.bytes)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Sends the client frames with their recorded timing and returns whether the server's frames
+/// matched the recording.
+fn replay(path: &str, server_address: &str) -> Result<bool, String> {
+    let recording =
+        fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+    let frames = parse_recording(&recording).map_err(|e| format!("{}: {}", path, e))?;
+    let (sender, replies) = mpsc::channel();
+    let mut connections: HashMap<usize, TcpStream> = HashMap::new();
+    let first = frames.first().map_or(Duration::ZERO, |frame| frame.elapsed);
+    let started = Instant::now();
+    for frame in frames
+        .iter()
+        .filter(|frame| frame.direction == Direction::CLIENTTOSERVER)
+    {
+        thread::sleep((frame.elapsed - first).saturating_sub(started.elapsed()));
+        let stream = match connections.entry(frame.connection) {
+            Entry::Occupied(stream) => stream.into_mut(),
+            Entry::Vacant(entry) => {
+                let stream = TcpStream::connect(server_address)
+                    .map_err(|e| format!("Unable to reach {}: {}", server_address, e))?;
+                let reader = stream.try_clone().map_err(|e| e.to_string())?;
+                let sender = sender.clone();
+                let connection = frame.connection;
+                thread::spawn(move || read_replies(connection, reader, sender));
+                entry.insert(stream)
+            }
+        };
+        stream
+            .write_all(&frame.bytes)
+            .map_err(|e| format!("Connection {} failed: {}", frame.connection, e))?;
+        println!("sent {} {}", frame.connection, frame_text(&frame.bytes));
+    }
+
+    let expected = frames
+        .iter()
+        .filter(|frame| frame.direction == Direction::SERVERTOCLIENT)
+        .count();
+    let mut actual = Vec::new();
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    while actual.len() < expected {
+        match replies.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(reply) => actual.push(reply),
+            Err(_) => break,
+        }
+    }
+    while let Ok(reply) = replies.recv_timeout(EXTRA_REPLY_WAIT) {
+        actual.push(reply);
+    }
+
+    let mismatches = diff(&frames, &actual);
+    for mismatch in &mismatches {
+        println!("{}", mismatch);
+    }
+    println!(
+        "{} server frames recorded, {} received, {} differed",
+        expected,
+        actual.len(),
+        mismatches.len()
+    );
+    Ok(mismatches.is_empty())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args[..] {
+        ["record", port, server_address, path] => record(port, server_address, path).map(|_| true),
+        ["replay", path, server_address] => replay(path, server_address),
+        ["faults", port, server_address, spec] => match spec.parse::<Faults>() {
+            Ok(faults) => inject_faults(port, server_address, faults).map(|_| true),
+            Err(e) => Err(format!("Invalid fault spec: {}", e)),
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,22 +1,60 @@
-use jdcp;
+use jdcp::{
+    character::{
+        character_data::{
+            class_type::ClassType, health_points::HealthPoints, race_kind::RaceKind,
+            stat_block::StatBlock,
+        },
+        Character,
+    },
+    framing::FrameSplitter,
+    session::{ClientId, Session},
+};
 use std::{
+    collections::HashMap,
     env::args,
     io::{Read, Write},
     net::{Shutdown, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
     thread,
 };
 
-fn handle_client(mut stream: TcpStream) {
-    let mut buff = [0 as u8; 50];
+#[derive(Default)]
+struct Shared {
+    session: Session,
+    clients: HashMap<ClientId, TcpStream>,
+}
+
+fn handle_client(id: ClientId, mut stream: TcpStream, shared: Arc<Mutex<Shared>>) {
+    let mut buff = [0_u8; 50];
+    let mut splitter = FrameSplitter::default();
     while match stream.read(&mut buff) {
+        Ok(0) => false,
         Ok(size) => {
-            println!("Recieved message of {} bytes: {:?}", size, buff);
-            let jdcp_message = jdcp::decode_jdcp(&buff).expect("Unable to decode message");
-
-            println!("Leftover Buffer: {:?}", jdcp_message.0);
-            println!("Message: {:?}", jdcp_message.1);
-
-            stream.write(b"okay...").unwrap();
+            println!("Recieved message of {} bytes: {:?}", size, &buff[..size]);
+            for frame in splitter.push(&buff[..size]) {
+                let Some(message) = frame.message else {
+                    println!(
+                        "Skipped {} bytes to the next message: {:?}",
+                        frame.bytes.len(),
+                        frame.reason
+                    );
+                    continue;
+                };
+                println!("Message: {}", message);
+                let mut shared = shared.lock().unwrap();
+                match shared.session.handle(id, message) {
+                    Ok(outgoing) => {
+                        for reply in outgoing {
+                            if let Some(client) = shared.clients.get_mut(&reply.client) {
+                                client.write_all(&reply.bytes).unwrap_or_else(|e| {
+                                    println!("Unable to reach client {}: {}", reply.client, e)
+                                });
+                            }
+                        }
+                    }
+                    Err(e) => println!("Unable to handle message: {}", e),
+                }
+            }
             true
         }
         Err(_) => {
@@ -28,20 +66,61 @@ fn handle_client(mut stream: TcpStream) {
             false
         }
     } {}
+    let mut shared = shared.lock().unwrap();
+    shared.session.disconnect(id);
+    shared.clients.remove(&id);
 }
 
 fn main() {
     if args().count() < 2 {
         panic!("Not enough arguments")
     }
-    let port = args().last().unwrap();
+    let port = args().next_back().unwrap();
+    #[cfg(feature = "registry")]
+    if let Some(path) = args().nth(2).and(args().nth(1)) {
+        use jdcp::character::registry::Registry;
+        match Registry::load(&path).and_then(Registry::install) {
+            Ok(()) => println!("Loaded custom classes and races from {}", path),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    #[cfg(not(feature = "registry"))]
+    if args().count() > 2 {
+        panic!("Loading a registry needs the registry feature")
+    }
     let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).unwrap();
 
+    let shared = Arc::new(Mutex::new(Shared::default()));
+    shared
+        .lock()
+        .unwrap()
+        .session
+        .insert_character(Character::new(
+            "Bart".to_string(),
+            RaceKind::HUMAN,
+            ClassType::FIGHTER,
+            5,
+            StatBlock::new(16, 12, 14, 10, 10, 8),
+            HealthPoints {
+                current: 42,
+                max: 42,
+            },
+            30,
+            "".to_string(),
+        ))
+        .unwrap();
+
     println!("Server listening on port {}", port);
-    for stream in listener.incoming() {
+    for (id, stream) in listener.incoming().enumerate() {
         match stream {
             Ok(stream) => {
-                thread::spawn(move || handle_client(stream));
+                let shared = Arc::clone(&shared);
+                shared
+                    .lock()
+                    .unwrap()
+                    .clients
+                    .insert(id, stream.try_clone().unwrap());
+                thread::spawn(move || handle_client(id, stream, shared));
             }
             Err(e) => {
                 println!("Error: {}", e);
@@ -0,0 +1,710 @@
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use nom::{
+    bytes::complete::take,
+    error::{context, VerboseError},
+    multi::many0,
+    number::{complete as number, Endianness},
+    sequence::tuple,
+    IResult,
+};
+
+use crate::{conformance::ErrorCategory, framing::FrameSplitter, message::Message};
+
+type CaptureRes<'a, T> = IResult<&'a [u8], T, VerboseError<&'a [u8]>>;
+
+const PCAP_MICROS: u32 = 0xA1B2_C3D4;
+const PCAP_NANOS: u32 = 0xA1B2_3C4D;
+const PCAPNG_SECTION: u32 = 0x0A0D_0D0A;
+const PCAPNG_BYTE_ORDER: u32 = 0x1A2B_3C4D;
+const PCAPNG_INTERFACE: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+const PCAPNG_TSRESOL: u16 = 9;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+#[derive(Debug, PartialEq)]
+pub enum CaptureError {
+    UnknownFormat,
+    Malformed(String),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::UnknownFormat => write!(f, "Not a pcap or pcapng capture"),
+            CaptureError::Malformed(e) => write!(f, "Malformed capture: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<nom::Err<VerboseError<&[u8]>>> for CaptureError {
+    fn from(e: nom::Err<VerboseError<&[u8]>>) -> Self {
+        CaptureError::Malformed(match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e
+                .errors
+                .iter()
+                .map(|(_, kind)| format!("{:?}", kind))
+                .collect::<Vec<String>>()
+                .join(", "),
+            nom::Err::Incomplete(_) => "unexpected end of file".to_string(),
+        })
+    }
+}
+
+/// A captured link layer frame, timestamped since the Unix epoch.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame {
+    pub timestamp: Duration,
+    pub link_type: u32,
+    pub data: Vec<u8>,
+}
+
+/// The parts of a TCP segment needed to put its stream back together.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Segment {
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub sequence: u32,
+    pub syn: bool,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CapturedMessage {
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub message: Message<'static>,
+}
+
+/// Bytes of a stream that didn't decode. `start` and `end` count from the first byte of the stream.
+#[derive(Debug, PartialEq)]
+pub struct UndecodableRange {
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub start: usize,
+    pub end: usize,
+    pub reason: &'static str,
+    /// What the decoder made of the bytes, `None` for bytes missing from the capture.
+    pub category: Option<ErrorCategory>,
+}
+
+/// Everything decoded from a capture, each list in timestamp order.
+#[derive(Debug, PartialEq, Default)]
+pub struct Timeline {
+    pub start: Duration,
+    pub messages: Vec<CapturedMessage>,
+    pub undecodable: Vec<UndecodableRange>,
+}
+
+fn pcap_record(i: &[u8], endian: Endianness, nanos: bool, link_type: u32) -> CaptureRes<'_, Frame> {
+    let (i, (seconds, fraction, captured_len, _original_len)) = context(
+        "pcap record header",
+        tuple((
+            number::u32(endian),
+            number::u32(endian),
+            number::u32(endian),
+            number::u32(endian),
+        )),
+    )(i)?;
+    let (i, data) = context("pcap record data", take(captured_len))(i)?;
+    let fraction = if nanos {
+        Duration::from_nanos(fraction.into())
+    } else {
+        Duration::from_micros(fraction.into())
+    };
+    Ok((
+        i,
+        Frame {
+            timestamp: Duration::from_secs(seconds.into()) + fraction,
+            link_type,
+            data: data.to_vec(),
+        },
+    ))
+}
+
+fn pcap_frames(capture: &[u8], endian: Endianness, nanos: bool) -> CaptureRes<'_, Vec<Frame>> {
+    let (i, (_magic, _major, _minor, _zone, _sigfigs, _snaplen, link_type)) = context(
+        "pcap header",
+        tuple((
+            take(4usize),
+            number::u16(endian),
+            number::u16(endian),
+            number::u32(endian),
+            number::u32(endian),
+            number::u32(endian),
+            number::u32(endian),
+        )),
+    )(capture)?;
+    // A record cut off by the capture being stopped is left over rather than failing the file.
+    many0(move |i| pcap_record(i, endian, nanos, link_type))(i)
+}
+
+/// Link type and ticks per second of a pcapng interface.
+fn pcapng_interface(body: &[u8], endian: Endianness) -> CaptureRes<'_, (u32, u64)> {
+    let (mut i, (link_type, _reserved, _snaplen)) = context(
+        "pcapng interface",
+        tuple((
+            number::u16(endian),
+            number::u16(endian),
+            number::u32(endian),
+        )),
+    )(body)?;
+    let mut resolution = 1_000_000;
+    while i.len() >= 4 {
+        let (rest, (code, len)) = tuple((number::u16(endian), number::u16(endian)))(i)?;
+        let (rest, value) = take(len)(rest)?;
+        if code == PCAPNG_TSRESOL {
+            resolution = match value.first() {
+                Some(exponent) if exponent & 0x80 == 0 => 10u64.saturating_pow((*exponent).into()),
+                Some(exponent) => 2u64.saturating_pow((exponent & 0x7F).into()),
+                None => resolution,
+            };
+        }
+        let padding = (4 - usize::from(len) % 4) % 4;
+        i = rest.get(padding..).unwrap_or_default();
+    }
+    Ok((i, (u32::from(link_type), resolution)))
+}
+
+fn pcapng_timestamp(high: u32, low: u32, resolution: u64) -> Duration {
+    let ticks = u128::from(high) << 32 | u128::from(low);
+    let resolution = u128::from(resolution.max(1));
+    Duration::from_nanos(
+        (ticks * 1_000_000_000 / resolution)
+            .try_into()
+            .unwrap_or(u64::MAX),
+    )
+}
+
+fn pcapng_frames(capture: &[u8]) -> Result<Vec<Frame>, CaptureError> {
+    let mut frames = Vec::new();
+    let mut interfaces: Vec<(u32, u64)> = Vec::new();
+    let mut endian = Endianness::Little;
+    let mut i = capture;
+    while i.len() >= 12 {
+        let (_, block_type) = number::le_u32(i)?;
+        if block_type == PCAPNG_SECTION {
+            let (_, byte_order) = number::le_u32(&i[8..])?;
+            endian = if byte_order == PCAPNG_BYTE_ORDER {
+                Endianness::Little
+            } else {
+                Endianness::Big
+            };
+            interfaces.clear();
+        }
+        let (_, (block_type, total_len)) = tuple((number::u32(endian), number::u32(endian)))(i)?;
+        let total_len = total_len as usize;
+        if total_len < 12 || total_len > i.len() {
+            return Err(CaptureError::Malformed(format!(
+                "block of {} bytes at offset {}",
+                total_len,
+                capture.len() - i.len()
+            )));
+        }
+        let body = &i[8..total_len - 4];
+        match block_type {
+            PCAPNG_INTERFACE => interfaces.push(pcapng_interface(body, endian)?.1),
+            PCAPNG_ENHANCED_PACKET => {
+                let (rest, (interface, high, low, captured_len, _original_len)) =
+                    context(
+                        "pcapng enhanced packet",
+                        tuple((
+                            number::u32(endian),
+                            number::u32(endian),
+                            number::u32(endian),
+                            number::u32(endian),
+                            number::u32(endian),
+                        )),
+                    )(body)?;
+                let (_, data) = take(captured_len)(rest)?;
+                let (link_type, resolution) =
+                    interfaces.get(interface as usize).copied().ok_or_else(|| {
+                        CaptureError::Malformed(format!("no interface {}", interface))
+                    })?;
+                frames.push(Frame {
+                    timestamp: pcapng_timestamp(high, low, resolution),
+                    link_type,
+                    data: data.to_vec(),
+                });
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                let (data, original_len) = number::u32(endian)(body)?;
+                let (link_type, _) = interfaces
+                    .first()
+                    .copied()
+                    .ok_or_else(|| CaptureError::Malformed("no interface 0".to_string()))?;
+                frames.push(Frame {
+                    timestamp: Duration::ZERO,
+                    link_type,
+                    data: data[..data.len().min(original_len as usize)].to_vec(),
+                });
+            }
+            _ => {}
+        }
+        i = &i[total_len..];
+    }
+    Ok(frames)
+}
+
+/// Reads every frame of a pcap or pcapng capture.
+pub fn read_frames(capture: &[u8]) -> Result<Vec<Frame>, CaptureError> {
+    let magic = capture.get(..4).ok_or(CaptureError::UnknownFormat)?;
+    let magic = u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]);
+    let (endian, nanos) = match (magic, magic.swap_bytes()) {
+        (PCAPNG_SECTION, _) => return pcapng_frames(capture),
+        (PCAP_MICROS, _) => (Endianness::Little, false),
+        (PCAP_NANOS, _) => (Endianness::Little, true),
+        (_, PCAP_MICROS) => (Endianness::Big, false),
+        (_, PCAP_NANOS) => (Endianness::Big, true),
+        _ => return Err(CaptureError::UnknownFormat),
+    };
+    Ok(pcap_frames(capture, endian, nanos)?.1)
+}
+
+fn be_u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+/// The IP packet inside a frame, for the link types tcpdump writes.
+fn ip_packet(frame: &Frame) -> Option<&[u8]> {
+    match frame.link_type {
+        LINKTYPE_ETHERNET => {
+            let mut at = 12;
+            let mut ethertype = be_u16_at(&frame.data, at)?;
+            // Skip 802.1Q and 802.1ad VLAN tags.
+            while ethertype == 0x8100 || ethertype == 0x88A8 {
+                at += 4;
+                ethertype = be_u16_at(&frame.data, at)?;
+            }
+            frame.data.get(at + 2..)
+        }
+        LINKTYPE_LINUX_SLL => frame.data.get(16..),
+        LINKTYPE_NULL => frame.data.get(4..),
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(&frame.data),
+        _ => None,
+    }
+}
+
+fn tcp_segment(frame: &Frame) -> Option<Segment> {
+    let packet = ip_packet(frame)?;
+    let (source, destination, tcp) = match packet.first()? >> 4 {
+        4 => {
+            let header_len = usize::from(packet[0] & 0x0F) * 4;
+            let total_len = usize::from(be_u16_at(packet, 2)?);
+            // Fragments other than a whole datagram would need IP reassembly first.
+            if *packet.get(9)? != 6 || be_u16_at(packet, 6)? & 0x3FFF != 0 {
+                return None;
+            }
+            let address = |at: usize| -> Option<IpAddr> {
+                let b = packet.get(at..at + 4)?;
+                Some(Ipv4Addr::new(b[0], b[1], b[2], b[3]).into())
+            };
+            (
+                address(12)?,
+                address(16)?,
+                packet.get(header_len..total_len.min(packet.len()))?,
+            )
+        }
+        6 => {
+            if *packet.get(6)? != 6 {
+                return None;
+            }
+            let payload_len = usize::from(be_u16_at(packet, 4)?);
+            let address = |at: usize| -> Option<IpAddr> {
+                let b: [u8; 16] = packet.get(at..at + 16)?.try_into().ok()?;
+                Some(Ipv6Addr::from(b).into())
+            };
+            (
+                address(8)?,
+                address(24)?,
+                packet.get(40..(40 + payload_len).min(packet.len()))?,
+            )
+        }
+        _ => return None,
+    };
+    let header_len = usize::from(tcp.get(12)? >> 4) * 4;
+    let sequence = tcp.get(4..8)?;
+    Some(Segment {
+        timestamp: frame.timestamp,
+        source: SocketAddr::new(source, be_u16_at(tcp, 0)?),
+        destination: SocketAddr::new(destination, be_u16_at(tcp, 2)?),
+        sequence: u32::from_be_bytes([sequence[0], sequence[1], sequence[2], sequence[3]]),
+        syn: tcp.get(13)? & 0x02 != 0,
+        payload: tcp.get(header_len..)?.to_vec(),
+    })
+}
+
+/// TCP segments of every frame that holds one, in capture order.
+pub fn tcp_segments(frames: &[Frame]) -> Vec<Segment> {
+    frames.iter().filter_map(tcp_segment).collect()
+}
+
+/// Contiguous bytes of a stream with the time each piece arrived.
+struct Run {
+    start: usize,
+    bytes: Vec<u8>,
+    arrivals: Vec<(usize, Duration)>,
+}
+
+impl Run {
+    fn arrival(&self, offset: usize) -> Duration {
+        self.arrivals
+            .iter()
+            .take_while(|(start, _)| *start <= offset)
+            .last()
+            .map_or(Duration::ZERO, |(_, timestamp)| *timestamp)
+    }
+}
+
+/// Orders one direction's segments by sequence number, dropping retransmitted bytes. Gaps in the
+/// capture split the stream into runs.
+fn reassemble(segments: &[&Segment]) -> Vec<Run> {
+    let Some(first) = segments.first() else {
+        return Vec::new();
+    };
+    let initial = segments
+        .iter()
+        .find(|segment| segment.syn)
+        .map_or(first.sequence, |syn| syn.sequence.wrapping_add(1));
+    let mut ordered: Vec<(i64, &Segment)> = segments
+        .iter()
+        .filter(|segment| !segment.payload.is_empty())
+        .map(|segment| {
+            let relative = i64::from(segment.sequence.wrapping_sub(initial) as i32);
+            (relative, *segment)
+        })
+        .collect();
+    // Stable, so of two segments at the same sequence number the first captured comes first.
+    ordered.sort_by_key(|(relative, _)| *relative);
+    let base = ordered.first().map_or(0, |(relative, _)| *relative);
+    let mut runs: Vec<Run> = Vec::new();
+    for (relative, segment) in ordered {
+        let offset = (relative - base) as usize;
+        match runs.last_mut() {
+            Some(run) if offset <= run.start + run.bytes.len() => {
+                let overlap = run.start + run.bytes.len() - offset;
+                if overlap < segment.payload.len() {
+                    run.arrivals.push((run.bytes.len(), segment.timestamp));
+                    run.bytes.extend_from_slice(&segment.payload[overlap..]);
+                }
+            }
+            _ => runs.push(Run {
+                start: offset,
+                bytes: segment.payload.clone(),
+                arrivals: vec![(0, segment.timestamp)],
+            }),
+        }
+    }
+    runs
+}
+
+fn decode_run(run: &Run, source: SocketAddr, destination: SocketAddr, timeline: &mut Timeline) {
+    let mut splitter = FrameSplitter::default();
+    let mut at = 0;
+    for frame in splitter.push(&run.bytes) {
+        let end = at + frame.bytes.len();
+        match frame.message {
+            Some(message) => timeline.messages.push(CapturedMessage {
+                timestamp: run.arrival(end - 1),
+                source,
+                destination,
+                message,
+            }),
+            None => timeline.undecodable.push(UndecodableRange {
+                timestamp: run.arrival(at),
+                source,
+                destination,
+                start: run.start + at,
+                end: run.start + end,
+                reason: "undecodable",
+                category: frame.reason,
+            }),
+        }
+        at = end;
+    }
+    if !splitter.pending().is_empty() {
+        timeline.undecodable.push(UndecodableRange {
+            timestamp: run.arrival(at),
+            source,
+            destination,
+            start: run.start + at,
+            end: run.start + run.bytes.len(),
+            reason: "incomplete",
+            category: Some(ErrorCategory::INCOMPLETE),
+        });
+    }
+}
+
+/// Decodes the JDCP messages sent either way over TCP connections to or from `port`.
+pub fn extract_messages(capture: &[u8], port: u16) -> Result<Timeline, CaptureError> {
+    let frames = read_frames(capture)?;
+    let segments = tcp_segments(&frames);
+    let mut flows: Vec<((SocketAddr, SocketAddr), Vec<&Segment>)> = Vec::new();
+    for segment in segments
+        .iter()
+        .filter(|segment| segment.source.port() == port || segment.destination.port() == port)
+    {
+        let key = (segment.source, segment.destination);
+        match flows.iter_mut().find(|(flow, _)| *flow == key) {
+            Some((_, flow)) => flow.push(segment),
+            None => flows.push((key, vec![segment])),
+        }
+    }
+    let mut timeline = Timeline {
+        start: frames
+            .first()
+            .map_or(Duration::ZERO, |frame| frame.timestamp),
+        ..Timeline::default()
+    };
+    for ((source, destination), flow) in flows {
+        let runs = reassemble(&flow);
+        for (previous, run) in runs.iter().zip(runs.iter().skip(1)) {
+            timeline.undecodable.push(UndecodableRange {
+                timestamp: run.arrival(0),
+                source,
+                destination,
+                start: previous.start + previous.bytes.len(),
+                end: run.start,
+                reason: "missing from capture",
+                category: None,
+            });
+        }
+        for run in &runs {
+            decode_run(run, source, destination, &mut timeline);
+        }
+    }
+    timeline.messages.sort_by_key(|captured| captured.timestamp);
+    timeline.undecodable.sort_by_key(|range| range.timestamp);
+    Ok(timeline)
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_capture_tests {
+    use super::*;
+    use crate::message::MessageType;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    /// Ethernet, IPv4 and TCP headers around `payload`.
+    fn frame(from_client: bool, sequence: u32, syn: bool, payload: &[u8]) -> Vec<u8> {
+        let (source, destination, ports) = if from_client {
+            (CLIENT, SERVER, [0xD4, 0x31, 0x30, 0x39])
+        } else {
+            (SERVER, CLIENT, [0x30, 0x39, 0xD4, 0x31])
+        };
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame.extend_from_slice(&[0x45, 0]);
+        frame.extend_from_slice(&(40 + payload.len() as u16).to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+        frame.extend_from_slice(&source);
+        frame.extend_from_slice(&destination);
+        frame.extend_from_slice(&ports);
+        frame.extend_from_slice(&sequence.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 0, 0x50, if syn { 0x02 } else { 0x18 }]);
+        frame.extend_from_slice(&[0xFF, 0xFF, 0, 0, 0, 0]);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn pcap(frames: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut capture = Vec::new();
+        for field in [PCAP_MICROS, 0x0004_0002, 0, 0, 65535, LINKTYPE_ETHERNET] {
+            capture.extend_from_slice(&field.to_le_bytes());
+        }
+        for (micros, frame) in frames {
+            for field in [
+                1_700_000_000,
+                *micros,
+                frame.len() as u32,
+                frame.len() as u32,
+            ] {
+                capture.extend_from_slice(&field.to_le_bytes());
+            }
+            capture.extend_from_slice(frame);
+        }
+        capture
+    }
+
+    fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let padded = body.len().next_multiple_of(4);
+        let total = (padded + 12) as u32;
+        let mut block = [block_type.to_be_bytes(), total.to_be_bytes()].concat();
+        block.extend_from_slice(body);
+        block.resize(8 + padded, 0);
+        block.extend_from_slice(&total.to_be_bytes());
+        block
+    }
+
+    fn pcapng(frames: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let section = [
+            PCAPNG_BYTE_ORDER.to_be_bytes().to_vec(),
+            vec![0, 1, 0, 0],
+            vec![0xFF; 8],
+        ]
+        .concat();
+        let interface = [
+            vec![0, 1, 0, 0, 0, 0, 0xFF, 0xFF],
+            vec![0, 9, 0, 1, 9, 0, 0, 0],
+            vec![0, 0, 0, 0],
+        ]
+        .concat();
+        let mut capture = [
+            pcapng_block(PCAPNG_SECTION, &section),
+            pcapng_block(PCAPNG_INTERFACE, &interface),
+        ]
+        .concat();
+        for (micros, frame) in frames {
+            let nanos = u64::from(*micros) * 1_000;
+            let mut body = Vec::new();
+            for field in [
+                0,
+                (nanos >> 32) as u32,
+                nanos as u32,
+                frame.len() as u32,
+                frame.len() as u32,
+            ] {
+                body.extend_from_slice(&field.to_be_bytes());
+            }
+            body.extend_from_slice(frame);
+            capture.extend(pcapng_block(PCAPNG_ENHANCED_PACKET, &body));
+        }
+        capture
+    }
+
+    fn conversation() -> Vec<(u32, Vec<u8>)> {
+        let request = b"jdcp-\xAABart\x00\x06\x00\x00";
+        let response = b"jdcp-\xBBBart\x00\x06\x02\x00\x22\x2A";
+        vec![
+            (0, frame(true, 99, true, b"")),
+            (10, frame(true, 100, false, &request[..6])),
+            // Resent with more bytes, then the rest of the request arrives out of order.
+            (30, frame(true, 107, false, b"art\x00\x06\x00\x00")),
+            (20, frame(true, 100, false, &request[..7])),
+            (40, frame(false, 5000, false, response)),
+            (50, frame(true, 114, false, b"garbage")),
+            (60, frame(true, 121, false, &request[..8])),
+        ]
+    }
+
+    fn assert_conversation(timeline: &Timeline) {
+        let server: SocketAddr = "10.0.0.2:12345".parse().unwrap();
+        assert_eq!(timeline.start, Duration::from_secs(1_700_000_000));
+        assert_eq!(
+            timeline
+                .messages
+                .iter()
+                .map(|captured| (
+                    captured.timestamp.subsec_micros(),
+                    captured.message.to_string()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (30, "REQUEST Bart HP".to_string()),
+                (40, "RESPONSE Bart HP 34/42".to_string()),
+            ]
+        );
+        assert_eq!(timeline.messages[0].destination, server);
+        assert_eq!(
+            timeline.messages[1].message.message_type,
+            MessageType::RESPONSE
+        );
+        assert_eq!(
+            timeline
+                .undecodable
+                .iter()
+                .map(|range| (range.start, range.end, range.reason, range.category))
+                .collect::<Vec<_>>(),
+            vec![
+                (14, 21, "undecodable", Some(ErrorCategory::BADPREAMBLE)),
+                (21, 29, "incomplete", Some(ErrorCategory::INCOMPLETE)),
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_messages_from_pcap() {
+        assert_conversation(&extract_messages(&pcap(&conversation()), 12345).unwrap());
+    }
+
+    #[test]
+    fn extracts_messages_from_pcapng() {
+        let capture = pcapng(&conversation());
+        let frames = read_frames(&capture).unwrap();
+        assert_eq!(frames.len(), 7);
+        assert_eq!(frames[1].timestamp, Duration::from_micros(10));
+        let timeline = extract_messages(&capture, 12345).unwrap();
+        assert_eq!(
+            timeline
+                .messages
+                .iter()
+                .map(|captured| captured.message.to_string())
+                .collect::<Vec<_>>(),
+            vec!["REQUEST Bart HP", "RESPONSE Bart HP 34/42"]
+        );
+        assert_eq!(timeline.undecodable.len(), 2);
+    }
+
+    #[test]
+    fn reports_gaps_and_rejects_other_files() {
+        let timeline = extract_messages(
+            &pcap(&[
+                (0, frame(true, 100, false, b"jdcp-\xAABart\x00\x06\x00\x00")),
+                (
+                    10,
+                    frame(true, 123, false, b"jdcp-\xAABart\x00\x06\x00\x00"),
+                ),
+            ]),
+            12345,
+        )
+        .unwrap();
+        assert_eq!(timeline.messages.len(), 2);
+        assert_eq!(
+            timeline.undecodable[0],
+            UndecodableRange {
+                timestamp: Duration::new(1_700_000_000, 10_000),
+                source: "10.0.0.1:54321".parse().unwrap(),
+                destination: "10.0.0.2:12345".parse().unwrap(),
+                start: 14,
+                end: 23,
+                reason: "missing from capture",
+                category: None,
+            }
+        );
+        assert_eq!(read_frames(b"jdcp-"), Err(CaptureError::UnknownFormat));
+    }
+
+    #[test]
+    fn reads_the_fixture_capture() {
+        let capture = std::fs::read("fixtures/capture/hp.pcap").unwrap();
+        let timeline = extract_messages(&capture, 12345).unwrap();
+        assert_eq!(timeline.messages.len(), 3);
+        assert_eq!(
+            timeline.messages[2].message.to_string(),
+            "WRITE Bart HP 30/42"
+        );
+        assert_eq!(
+            (timeline.undecodable[0].start, timeline.undecodable[0].end),
+            (14, 31)
+        );
+    }
+}
@@ -1,23 +1,82 @@
-use self::character_data::{
-    class_type::ClassType, health_points::HealthPoints, race_kind::RaceKind, stat_block::StatBlock,
+use std::fmt;
+
+use self::{
+    character_data::{
+        class_type::ClassType,
+        conditions::Conditions,
+        health_points::HealthPoints,
+        race_kind::RaceKind,
+        skill_proficiencies::SkillProficiencies,
+        stat_block::{Ability, StatBlock},
+        CharacterData,
+    },
+    derived_stats::Equipment,
+    experience::{HpRoll, LevelUp},
+    skills::Skill,
+    spellcasting::{Spellcasting, SpellcastingError},
 };
+use crate::message::InfoType;
 
+pub mod ability_scores;
 pub mod character_data;
+pub mod derived_stats;
+pub mod experience;
+#[cfg(feature = "foundry")]
+pub mod foundry;
+pub mod registry;
+pub mod rules;
+pub mod skills;
+pub mod spellcasting;
+
+#[derive(Debug, PartialEq)]
+pub enum CharacterError {
+    ReadOnly(InfoType),
+    Spellcasting(SpellcastingError),
+    /// An XP write lower than the character's level needs.
+    XpBelowLevel(u32),
+}
+
+impl fmt::Display for CharacterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CharacterError::ReadOnly(info_type) => write!(f, "{:?} can not be written", info_type),
+            CharacterError::Spellcasting(e) => write!(f, "{}", e),
+            CharacterError::XpBelowLevel(xp) => {
+                write!(f, "{} XP is less than the current level needs", xp)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CharacterError {}
+
+impl From<SpellcastingError> for CharacterError {
+    fn from(value: SpellcastingError) -> Self {
+        CharacterError::Spellcasting(value)
+    }
+}
 
 #[derive(PartialEq, Debug)]
-struct Character {
-    name: String,
-    race: RaceKind,
-    class: ClassType,
-    level: u8,
-    stats: StatBlock,
-    hp: HealthPoints,
-    age: u16,
-    description: String,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Character {
+    pub name: String,
+    pub race: RaceKind,
+    pub class: ClassType,
+    pub level: u8,
+    pub stats: StatBlock,
+    pub hp: HealthPoints,
+    pub age: u16,
+    pub description: String,
+    pub spellcasting: Spellcasting,
+    pub conditions: Conditions,
+    pub skills: SkillProficiencies,
+    pub equipment: Equipment,
+    pub xp: u32,
 }
 
 impl Character {
-    fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
         name: String,
         race: RaceKind,
         class: ClassType,
@@ -27,6 +86,8 @@ impl Character {
         age: u16,
         description: String,
     ) -> Character {
+        let spellcasting = Spellcasting::new(&class, level);
+        let xp = experience::XP_THRESHOLDS[usize::from(level.clamp(1, experience::MAX_LEVEL) - 1)];
         Character {
             name,
             race,
@@ -36,13 +97,148 @@ impl Character {
             hp,
             age,
             description,
+            spellcasting,
+            conditions: Conditions::default(),
+            skills: SkillProficiencies::default(),
+            equipment: Equipment::default(),
+            xp,
+        }
+    }
+
+    /// Gains a level, adding hit points from the class hit die and constitution modifier.
+    /// Returns `None` once the character is already level 20.
+    pub fn level_up(&mut self, roll: HpRoll) -> Option<LevelUp> {
+        if self.level >= experience::MAX_LEVEL {
+            return None;
+        }
+        self.level += 1;
+        let hp_gained = experience::hp_gain(&self.class, &self.stats, roll);
+        self.hp.max = self.hp.max.saturating_add(hp_gained);
+        self.hp.current = self.hp.current.saturating_add(hp_gained);
+        self.spellcasting.set_level(&self.class, self.level);
+        Some(LevelUp {
+            level: self.level,
+            hp_gained,
+            features: experience::features(&self.class, self.level),
+        })
+    }
+
+    /// Adds experience points and takes average hit points for every threshold crossed.
+    pub fn award_xp(&mut self, xp: u32) -> Vec<LevelUp> {
+        self.xp = self.xp.saturating_add(xp);
+        let mut level_ups = Vec::new();
+        while self.level < experience::level_for_xp(self.xp) {
+            match self.level_up(HpRoll::AVERAGE) {
+                Some(level_up) => level_ups.push(level_up),
+                None => break,
+            }
+        }
+        level_ups
+    }
+
+    pub fn skill_bonus(&self, skill: Skill) -> i8 {
+        skills::skill_bonus(skill, &self.stats, &self.skills, self.level)
+    }
+
+    /// Ability modifier plus the proficiency bonus for the class's saving throw proficiencies.
+    /// Saturates at `i8::MAX`.
+    pub fn saving_throw_bonus(&self, ability: Ability) -> i8 {
+        let modifier = self.stats.modifier(ability);
+        if self.class.rules().saving_throws.contains(&ability) {
+            modifier.saturating_add(skills::proficiency_bonus(self.level))
+        } else {
+            modifier
         }
     }
+
+    pub fn passive_perception(&self) -> i8 {
+        skills::passive_perception(&self.stats, &self.skills, self.level)
+    }
+
+    /// Current value of a readable info type, `None` for write-only info types.
+    pub fn info(&self, info_type: &InfoType) -> Option<CharacterData> {
+        match info_type {
+            InfoType::STATS => Some(CharacterData::STATS(self.stats.clone())),
+            InfoType::AGE => Some(CharacterData::AGE(self.age)),
+            InfoType::CLASS => Some(CharacterData::CLASS(self.class.clone())),
+            InfoType::RACE => Some(CharacterData::RACE(self.race.clone())),
+            InfoType::LEVEL => Some(CharacterData::LEVEL(self.level)),
+            InfoType::HP => Some(CharacterData::HP(self.hp.clone())),
+            InfoType::SPELLSLOTS => {
+                Some(CharacterData::SPELLSLOTS(self.spellcasting.slots.clone()))
+            }
+            InfoType::SPELLS => Some(CharacterData::SPELLS(self.spellcasting.spells.clone())),
+            InfoType::CONDITIONS => Some(CharacterData::CONDITIONS(self.conditions)),
+            InfoType::SKILLS => Some(CharacterData::SKILLS(self.skills)),
+            InfoType::COMBAT => Some(CharacterData::COMBAT(derived_stats::combat_stats(
+                &self.class,
+                &self.race,
+                &self.stats,
+                &self.equipment,
+            ))),
+            InfoType::XP => Some(CharacterData::XP(self.xp)),
+            InfoType::CASTSPELL
+            | InfoType::REST
+            | InfoType::ADDCONDITIONS
+            | InfoType::REMOVECONDITIONS
+            | InfoType::AWARDXP
+            | InfoType::ROLL
+            | InfoType::ROLLRESULT
+            | InfoType::ROLLLOG
+            | InfoType::Custom(_)
+            | InfoType::Unknown(_) => None,
+        }
+    }
+
+    /// Applies a write to the character, keeping derived spell slots in step with class and level.
+    /// The level only changes through XP, which levels the character up past each threshold.
+    pub fn apply(&mut self, data: CharacterData) -> Result<(), CharacterError> {
+        match data {
+            CharacterData::STATS(stats) => self.stats = stats,
+            CharacterData::AGE(age) => self.age = age,
+            CharacterData::CLASS(class) => {
+                self.spellcasting.set_level(&class, self.level);
+                self.class = class;
+            }
+            CharacterData::RACE(race) => self.race = race,
+            CharacterData::HP(hp) => self.hp = hp,
+            CharacterData::SPELLSLOTS(slots) => self.spellcasting.slots = slots,
+            CharacterData::SPELLS(spells) => self.spellcasting.spells = spells,
+            CharacterData::CASTSPELL(slot_level) => self.spellcasting.cast(slot_level)?,
+            CharacterData::REST(rest) => self.spellcasting.rest(&rest),
+            CharacterData::CONDITIONS(conditions) => self.conditions = conditions,
+            CharacterData::ADDCONDITIONS(conditions) => self.conditions.add(&conditions),
+            CharacterData::REMOVECONDITIONS(conditions) => self.conditions.remove(&conditions),
+            CharacterData::SKILLS(skills) => self.skills = skills,
+            CharacterData::LEVEL(_) => return Err(CharacterError::ReadOnly(InfoType::LEVEL)),
+            CharacterData::COMBAT(_) => return Err(CharacterError::ReadOnly(InfoType::COMBAT)),
+            CharacterData::XP(xp) => {
+                let level = self.level.clamp(1, experience::MAX_LEVEL);
+                if xp < experience::XP_THRESHOLDS[usize::from(level - 1)] {
+                    return Err(CharacterError::XpBelowLevel(xp));
+                }
+                self.xp = self.xp.min(xp);
+                self.award_xp(xp - self.xp);
+            }
+            CharacterData::AWARDXP(xp) => {
+                self.award_xp(xp);
+            }
+            CharacterData::ROLL(_)
+            | CharacterData::ROLLRESULT(_)
+            | CharacterData::ROLLLOG(_)
+            | CharacterData::Custom(_)
+            | CharacterData::Unknown { .. } => {
+                return Err(CharacterError::ReadOnly(data.info_type()))
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod character_tests {
     use super::*;
+    use crate::character::character_data::{rest_kind::RestKind, spell_slots::SpellSlots};
 
     #[test]
     fn character_new_functions() {
@@ -86,8 +282,166 @@ mod character_tests {
                     max: 60
                 },
                 age: 2420u16,
-                description: "Some weird description".to_string()
+                description: "Some weird description".to_string(),
+                spellcasting: Spellcasting::default(),
+                conditions: Conditions::default(),
+                skills: SkillProficiencies::default(),
+                equipment: Equipment::default(),
+                xp: 14000,
             }
         )
     }
+
+    #[test]
+    fn character_casts_and_rests() {
+        let mut character = Character::new(
+            "Merlin".to_string(),
+            RaceKind::HUMAN,
+            ClassType::WIZARD,
+            3u8,
+            StatBlock::new(8, 14, 12, 17, 13, 10),
+            HealthPoints {
+                current: 16,
+                max: 16,
+            },
+            75u16,
+            "".to_string(),
+        );
+        character.apply(CharacterData::CASTSPELL(2)).unwrap();
+        character.apply(CharacterData::CASTSPELL(2)).unwrap();
+        assert_eq!(
+            character.apply(CharacterData::CASTSPELL(2)),
+            Err(CharacterError::Spellcasting(
+                SpellcastingError::NoSlotsRemaining(2)
+            ))
+        );
+        character
+            .apply(CharacterData::REST(RestKind::LONG))
+            .unwrap();
+        assert_eq!(
+            character.info(&InfoType::SPELLSLOTS),
+            Some(CharacterData::SPELLSLOTS(SpellSlots {
+                max: [4, 2, 0, 0, 0, 0, 0, 0, 0],
+                used: [0; 9],
+                pact: Default::default(),
+            }))
+        );
+        assert_eq!(
+            character.apply(CharacterData::LEVEL(5)),
+            Err(CharacterError::ReadOnly(InfoType::LEVEL))
+        );
+        assert_eq!(
+            character.apply(CharacterData::XP(100)),
+            Err(CharacterError::XpBelowLevel(100))
+        );
+        character.apply(CharacterData::XP(6500)).unwrap();
+        assert_eq!(character.level, 5);
+        assert_eq!(character.spellcasting.remaining(3), 2);
+    }
+
+    #[test]
+    fn saving_throws_add_class_proficiency() {
+        let character = Character::new(
+            "Bart".to_string(),
+            RaceKind::HUMAN,
+            ClassType::FIGHTER,
+            5u8,
+            StatBlock::new(16, 12, 14, 10, 10, 8),
+            HealthPoints {
+                current: 42,
+                max: 42,
+            },
+            30u16,
+            "".to_string(),
+        );
+        assert_eq!(character.saving_throw_bonus(Ability::STRENGTH), 6);
+        assert_eq!(character.saving_throw_bonus(Ability::CONSTITUTION), 5);
+        assert_eq!(character.saving_throw_bonus(Ability::DEXTERITY), 1);
+        assert_eq!(character.saving_throw_bonus(Ability::CHARISMA), -1);
+
+        let mut mighty = character;
+        mighty.level = 20;
+        mighty.stats = StatBlock::new(255, 12, 14, 10, 10, 8);
+        assert_eq!(mighty.saving_throw_bonus(Ability::STRENGTH), 127);
+    }
+
+    #[test]
+    fn award_xp_crosses_thresholds() {
+        let mut character = Character::new(
+            "Conan".to_string(),
+            RaceKind::HUMAN,
+            ClassType::BARBARIAN,
+            1u8,
+            StatBlock::new(17, 13, 16, 8, 12, 10),
+            HealthPoints {
+                current: 15,
+                max: 15,
+            },
+            20u16,
+            "".to_string(),
+        );
+        assert_eq!(character.award_xp(250), vec![]);
+        let level_ups = character.award_xp(700);
+        assert_eq!(
+            level_ups,
+            vec![
+                LevelUp {
+                    level: 2,
+                    hp_gained: 10,
+                    features: vec![]
+                },
+                LevelUp {
+                    level: 3,
+                    hp_gained: 10,
+                    features: vec![]
+                }
+            ]
+        );
+        assert_eq!(character.level, 3);
+        assert_eq!(
+            character.hp,
+            HealthPoints {
+                current: 35,
+                max: 35
+            }
+        );
+        assert_eq!(
+            character.level_up(HpRoll::ROLLED(4)),
+            Some(LevelUp {
+                level: 4,
+                hp_gained: 7,
+                features: vec![experience::Feature::ABILITYSCOREIMPROVEMENT]
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn character_serializes_round_trip() {
+        let mut character = Character::new(
+            "Bart".to_string(),
+            RaceKind::HUMAN,
+            ClassType::FIGHTER,
+            5u8,
+            StatBlock::new(16, 12, 14, 10, 10, 8),
+            HealthPoints {
+                current: 42,
+                max: 42,
+            },
+            30u16,
+            "".to_string(),
+        );
+        character.conditions = Conditions::new(
+            character_data::conditions::ConditionFlags::POISONED
+                | character_data::conditions::ConditionFlags::PRONE,
+            1,
+        );
+        let json = serde_json::to_value(&character).unwrap();
+        assert_eq!(json["class"], "FIGHTER");
+        assert_eq!(json["conditions"]["flags"], "POISONED | PRONE");
+        assert_eq!(
+            serde_json::from_value::<Character>(json).unwrap(),
+            character
+        );
+    }
 }
@@ -0,0 +1,360 @@
+use std::fmt;
+
+use super::character_data::{
+    race_kind::RaceKind,
+    stat_block::{Ability, StatBlock},
+};
+use crate::dice::{DiceError, DiceExpression, DiceRng, Keep, RollResult, Term};
+
+pub const POINT_BUY_BUDGET: u8 = 27;
+pub const POINT_BUY_MIN: u8 = 8;
+pub const POINT_BUY_MAX: u8 = 15;
+pub const STANDARD_ARRAY: [u8; 6] = [15, 14, 13, 12, 10, 8];
+/// Expression rolled once per ability for rolled scores.
+pub const ROLLED_SCORE_EXPRESSION: &str = "4d6kh3";
+
+/// How a character's base ability scores, before racial increases, were produced.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AbilityScoreMethod {
+    POINTBUY,
+    STANDARDARRAY,
+    ROLLED,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AbilityScoreProblem {
+    OUTOFRANGE(Ability, u8),
+    OVERBUDGET(u8),
+    NOTSTANDARDARRAY,
+    MISSINGROLLS(usize),
+    NOTROLLED,
+}
+
+impl fmt::Display for AbilityScoreProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbilityScoreProblem::OUTOFRANGE(ability, score) => write!(
+                f,
+                "{:?} of {} is outside {} - {}",
+                ability, score, POINT_BUY_MIN, POINT_BUY_MAX
+            ),
+            AbilityScoreProblem::OVERBUDGET(spent) => write!(
+                f,
+                "{} points spent of a {} point budget",
+                spent, POINT_BUY_BUDGET
+            ),
+            AbilityScoreProblem::NOTSTANDARDARRAY => {
+                write!(f, "Scores are not the standard array {:?}", STANDARD_ARRAY)
+            }
+            AbilityScoreProblem::MISSINGROLLS(count) => {
+                write!(f, "Only {} of 6 ability score rolls were made", count)
+            }
+            AbilityScoreProblem::NOTROLLED => write!(f, "Scores do not match the rolls made"),
+        }
+    }
+}
+
+/// What was wrong, if anything, with a set of base ability scores.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValidationReport {
+    pub method: AbilityScoreMethod,
+    /// Points the scores would cost under point buy, `None` if any score is out of its range.
+    pub points_spent: Option<u8>,
+    pub problems: Vec<AbilityScoreProblem>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "Valid {:?} ability scores", self.method);
+        }
+        write!(f, "Invalid {:?} ability scores:", self.method)?;
+        for problem in &self.problems {
+            write!(f, " {}.", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Point buy cost of a single score, `None` outside 8 - 15.
+pub fn point_buy_cost(score: u8) -> Option<u8> {
+    match score {
+        POINT_BUY_MIN..=13 => Some(score - POINT_BUY_MIN),
+        14 => Some(7),
+        15 => Some(9),
+        _ => None,
+    }
+}
+
+fn sorted_scores(stats: &StatBlock) -> [u8; 6] {
+    let mut scores = Ability::ALL.map(|ability| stats.score(ability));
+    scores.sort_unstable_by(|a, b| b.cmp(a));
+    scores
+}
+
+/// Checks base ability scores were produced by `method`. `rolls` are the totals of the ability
+/// score rolls made for the character and are only looked at for `ROLLED`, where the scores have
+/// to match the first six of them. Any rolls after those are ignored.
+pub fn validate(stats: &StatBlock, method: AbilityScoreMethod, rolls: &[u8]) -> ValidationReport {
+    let points_spent = Ability::ALL
+        .iter()
+        .map(|ability| point_buy_cost(stats.score(*ability)))
+        .sum::<Option<u8>>();
+    let mut problems = Vec::new();
+    match method {
+        AbilityScoreMethod::POINTBUY => {
+            for ability in Ability::ALL {
+                let score = stats.score(ability);
+                if point_buy_cost(score).is_none() {
+                    problems.push(AbilityScoreProblem::OUTOFRANGE(ability, score));
+                }
+            }
+            match points_spent {
+                Some(spent) if spent > POINT_BUY_BUDGET => {
+                    problems.push(AbilityScoreProblem::OVERBUDGET(spent))
+                }
+                _ => (),
+            }
+        }
+        AbilityScoreMethod::STANDARDARRAY => {
+            if sorted_scores(stats) != STANDARD_ARRAY {
+                problems.push(AbilityScoreProblem::NOTSTANDARDARRAY);
+            }
+        }
+        AbilityScoreMethod::ROLLED => {
+            if rolls.len() < 6 {
+                problems.push(AbilityScoreProblem::MISSINGROLLS(rolls.len()));
+            } else {
+                let mut rolled = [0u8; 6];
+                rolled.copy_from_slice(&rolls[..6]);
+                rolled.sort_unstable_by(|a, b| b.cmp(a));
+                if sorted_scores(stats) != rolled {
+                    problems.push(AbilityScoreProblem::NOTROLLED);
+                }
+            }
+        }
+    }
+    ValidationReport {
+        method,
+        points_spent,
+        problems,
+    }
+}
+
+/// Hands out `scores` from highest to lowest following `priority`.
+pub fn assign(mut scores: [u8; 6], priority: [Ability; 6]) -> StatBlock {
+    scores.sort_unstable_by(|a, b| b.cmp(a));
+    let mut stats = StatBlock::new(0, 0, 0, 0, 0, 0);
+    for (ability, score) in priority.into_iter().zip(scores) {
+        stats.set_score(ability, score);
+    }
+    stats
+}
+
+pub fn standard_array(priority: [Ability; 6]) -> StatBlock {
+    assign(STANDARD_ARRAY, priority)
+}
+
+/// Spends the whole point buy budget maxing out the first three abilities in `priority`.
+pub fn point_buy(priority: [Ability; 6]) -> StatBlock {
+    assign([15, 15, 15, 8, 8, 8], priority)
+}
+
+/// Rolls 4d6 and drops the lowest die six times, once per ability.
+pub fn roll_ability_scores(rng: &mut impl DiceRng) -> Result<Vec<RollResult>, DiceError> {
+    let expression = DiceExpression {
+        terms: vec![Term::DICE {
+            count: 4,
+            sides: 6,
+            keep: Keep::HIGHEST(3),
+            negative: false,
+        }],
+    };
+    (0..6).map(|_| expression.roll(rng)).collect()
+}
+
+/// Base scores plus racial increases, some of which are negative. Half-elves also raise up to two other abilities from
+/// `choices` by 1, every other race ignores them.
+pub fn apply_racial_increases(
+    stats: &StatBlock,
+    race: &RaceKind,
+    choices: &[Ability],
+) -> StatBlock {
+    let mut increases = race.rules().ability_score_increases;
+    if *race == RaceKind::HALFELF {
+        let mut chosen: Vec<Ability> = Vec::new();
+        for ability in choices {
+            if *ability != Ability::CHARISMA && !chosen.contains(ability) && chosen.len() < 2 {
+                chosen.push(*ability);
+            }
+        }
+        increases.extend(chosen.into_iter().map(|ability| (ability, 1)));
+    }
+    let mut stats = stats.clone();
+    for (ability, increase) in increases {
+        stats.set_score(
+            ability,
+            stats.score(ability).saturating_add_signed(increase),
+        );
+    }
+    stats
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_ability_scores_tests {
+    use super::*;
+    use crate::dice::SeededRng;
+
+    const PRIORITY: [Ability; 6] = [
+        Ability::DEXTERITY,
+        Ability::CONSTITUTION,
+        Ability::WISDOM,
+        Ability::INTELLIGENCE,
+        Ability::CHARISMA,
+        Ability::STRENGTH,
+    ];
+
+    #[test]
+    fn generators_follow_priority() {
+        assert_eq!(
+            standard_array(PRIORITY),
+            StatBlock::new(8, 15, 14, 12, 13, 10)
+        );
+        assert_eq!(point_buy(PRIORITY), StatBlock::new(8, 15, 15, 8, 15, 8));
+        assert!(validate(&point_buy(PRIORITY), AbilityScoreMethod::POINTBUY, &[]).is_valid());
+        assert!(validate(&standard_array(PRIORITY), AbilityScoreMethod::POINTBUY, &[]).is_valid());
+    }
+
+    #[test]
+    fn point_buy_reports_range_and_budget() {
+        let report = validate(
+            &StatBlock::new(15, 15, 15, 15, 7, 8),
+            AbilityScoreMethod::POINTBUY,
+            &[],
+        );
+        assert_eq!(
+            report,
+            ValidationReport {
+                method: AbilityScoreMethod::POINTBUY,
+                points_spent: None,
+                problems: vec![AbilityScoreProblem::OUTOFRANGE(Ability::WISDOM, 7)],
+            }
+        );
+        let report = validate(
+            &StatBlock::new(15, 15, 15, 15, 8, 8),
+            AbilityScoreMethod::POINTBUY,
+            &[],
+        );
+        assert_eq!(report.points_spent, Some(36));
+        assert_eq!(report.problems, vec![AbilityScoreProblem::OVERBUDGET(36)]);
+    }
+
+    #[test]
+    fn standard_array_and_rolls_must_match() {
+        let stats = StatBlock::new(15, 14, 13, 12, 10, 10);
+        assert_eq!(
+            validate(&stats, AbilityScoreMethod::STANDARDARRAY, &[]).problems,
+            vec![AbilityScoreProblem::NOTSTANDARDARRAY]
+        );
+        assert_eq!(
+            validate(&stats, AbilityScoreMethod::ROLLED, &[15, 14]).problems,
+            vec![AbilityScoreProblem::MISSINGROLLS(2)]
+        );
+        assert!(validate(
+            &stats,
+            AbilityScoreMethod::ROLLED,
+            &[10, 12, 15, 10, 13, 14]
+        )
+        .is_valid());
+        assert_eq!(
+            validate(
+                &stats,
+                AbilityScoreMethod::ROLLED,
+                &[18, 12, 15, 10, 13, 14]
+            )
+            .problems,
+            vec![AbilityScoreProblem::NOTROLLED]
+        );
+        assert!(validate(
+            &stats,
+            AbilityScoreMethod::ROLLED,
+            &[10, 12, 15, 10, 13, 14, 18, 18]
+        )
+        .is_valid());
+        assert_eq!(
+            validate(
+                &stats,
+                AbilityScoreMethod::ROLLED,
+                &[18, 18, 10, 12, 15, 10, 13, 14]
+            )
+            .problems,
+            vec![AbilityScoreProblem::NOTROLLED]
+        );
+    }
+
+    #[test]
+    fn rolled_score_expression_matches_generator() {
+        assert_eq!(
+            ROLLED_SCORE_EXPRESSION.parse::<DiceExpression>().unwrap(),
+            DiceExpression {
+                terms: vec![Term::DICE {
+                    count: 4,
+                    sides: 6,
+                    keep: Keep::HIGHEST(3),
+                    negative: false,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn rolled_scores_keep_three_dice() {
+        let rolls = roll_ability_scores(&mut SeededRng::new(3)).unwrap();
+        assert_eq!(rolls.len(), 6);
+        for roll in rolls {
+            assert_eq!(roll.dice.iter().filter(|die| die.kept).count(), 3);
+            assert!((3..=18).contains(&roll.total));
+        }
+    }
+
+    #[test]
+    fn racial_increases_are_applied() {
+        let base = StatBlock::new(10, 10, 10, 10, 10, 10);
+        assert_eq!(
+            apply_racial_increases(&base, &RaceKind::HUMAN, &[]),
+            StatBlock::new(11, 11, 11, 11, 11, 11)
+        );
+        assert_eq!(
+            apply_racial_increases(&base, &RaceKind::TIEFLING, &[Ability::STRENGTH]),
+            StatBlock::new(10, 10, 10, 11, 10, 12)
+        );
+        assert_eq!(
+            apply_racial_increases(&base, &RaceKind::ORC, &[]),
+            StatBlock::new(12, 10, 11, 8, 10, 10)
+        );
+        assert_eq!(
+            apply_racial_increases(&base, &RaceKind::HALFORC, &[]),
+            StatBlock::new(12, 10, 11, 10, 10, 10)
+        );
+        assert_eq!(
+            apply_racial_increases(
+                &base,
+                &RaceKind::HALFELF,
+                &[
+                    Ability::CHARISMA,
+                    Ability::DEXTERITY,
+                    Ability::DEXTERITY,
+                    Ability::WISDOM,
+                    Ability::STRENGTH
+                ]
+            ),
+            StatBlock::new(10, 11, 10, 10, 11, 12)
+        );
+    }
+}
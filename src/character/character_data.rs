@@ -1,18 +1,12 @@
-use self::{
-    class_type::ClassType, health_points::HealthPoints, race_kind::RaceKind, stat_block::StatBlock,
-};
-
 pub mod class_type;
+pub mod combat_stats;
+pub mod conditions;
 pub mod health_points;
 pub mod race_kind;
+pub mod rest_kind;
+pub mod roll_record;
+pub mod skill_proficiencies;
+pub mod spell_slots;
 pub mod stat_block;
 
-#[derive(Debug, PartialEq)]
-pub enum CharacterData {
-    STATS(StatBlock),
-    AGE(u16),
-    CLASS(ClassType),
-    RACE(RaceKind),
-    LEVEL(u8),
-    HP(HealthPoints),
-}
\ No newline at end of file
+pub use crate::message::payload::CharacterData;
@@ -1,4 +1,10 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(remote = "Self")
+)]
+#[repr(u8)]
 pub enum ClassType {
     ARTIFICER = 1,
     BARBARIAN = 2,
@@ -14,6 +20,8 @@ pub enum ClassType {
     SORCERER = 12,
     WARLOCK = 13,
     WIZARD = 14,
+    /// A class this build doesn't know, e.g. homebrew or from a newer peer, kept as its byte.
+    Unknown(u8),
 }
 
 impl From<&[u8]> for ClassType {
@@ -33,13 +41,35 @@ impl From<&[u8]> for ClassType {
             Some(12) => ClassType::SORCERER,
             Some(13) => ClassType::WARLOCK,
             Some(14) => ClassType::WIZARD,
-            _ => unimplemented!("No other classes currently"),
+            Some(id) => ClassType::Unknown(*id),
+            None => ClassType::Unknown(0),
         }
     }
 }
 
 impl ClassType {
+    /// Every built in class, i.e. all but `Unknown`.
+    pub const ALL: [ClassType; 14] = [
+        ClassType::ARTIFICER,
+        ClassType::BARBARIAN,
+        ClassType::BARD,
+        ClassType::BLOODHUNTER,
+        ClassType::CLERIC,
+        ClassType::DRUID,
+        ClassType::FIGHTER,
+        ClassType::MONK,
+        ClassType::PALADIN,
+        ClassType::RANGER,
+        ClassType::ROGUE,
+        ClassType::SORCERER,
+        ClassType::WARLOCK,
+        ClassType::WIZARD,
+    ];
+
     pub fn discriminant(&self) -> u8 {
-        unsafe { *(self as *const Self as *const u8) }
+        match self {
+            ClassType::Unknown(id) => *id,
+            _ => unsafe { *(self as *const Self as *const u8) },
+        }
     }
 }
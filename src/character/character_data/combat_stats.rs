@@ -0,0 +1,27 @@
+/// Read only numbers derived from the rest of the character.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CombatStats {
+    pub armor_class: u8,
+    pub initiative: i8,
+    pub speed: u8,
+}
+
+impl CombatStats {
+    pub const SIZE: u16 = 3;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.armor_class, self.initiative as u8, self.speed].to_vec()
+    }
+}
+
+impl From<&[u8]> for CombatStats {
+    fn from(value: &[u8]) -> Self {
+        let byte = |at: usize| value.get(at).copied().unwrap_or(0);
+        CombatStats {
+            armor_class: byte(0),
+            initiative: byte(1) as i8,
+            speed: byte(2),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use bitflags::bitflags;
+
+bitflags! {
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct ConditionFlags: u16 {
+        const BLINDED = 1 << 0;
+        const CHARMED = 1 << 1;
+        const DEAFENED = 1 << 2;
+        const FRIGHTENED = 1 << 3;
+        const GRAPPLED = 1 << 4;
+        const INCAPACITATED = 1 << 5;
+        const INVISIBLE = 1 << 6;
+        const PARALYZED = 1 << 7;
+        const PETRIFIED = 1 << 8;
+        const POISONED = 1 << 9;
+        const PRONE = 1 << 10;
+        const RESTRAINED = 1 << 11;
+        const STUNNED = 1 << 12;
+        const UNCONSCIOUS = 1 << 13;
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conditions {
+    pub flags: ConditionFlags,
+    pub exhaustion: u8,
+}
+
+impl Conditions {
+    pub const SIZE: u16 = 3;
+    pub const MAX_EXHAUSTION: u8 = 6;
+
+    pub fn new(flags: ConditionFlags, exhaustion: u8) -> Self {
+        Conditions {
+            flags,
+            exhaustion: exhaustion.min(Self::MAX_EXHAUSTION),
+        }
+    }
+
+    /// Sets every flag in `other` and stacks its exhaustion levels, capped at 6.
+    pub fn add(&mut self, other: &Conditions) {
+        self.flags.insert(other.flags);
+        self.exhaustion = self
+            .exhaustion
+            .saturating_add(other.exhaustion)
+            .min(Self::MAX_EXHAUSTION);
+    }
+
+    /// Clears every flag in `other` and removes its exhaustion levels.
+    pub fn remove(&mut self, other: &Conditions) {
+        self.flags.remove(other.flags);
+        self.exhaustion = self.exhaustion.saturating_sub(other.exhaustion);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let flags = self.flags.bits().to_le_bytes();
+        [flags[0], flags[1], self.exhaustion].to_vec()
+    }
+}
+
+impl From<&[u8]> for Conditions {
+    fn from(value: &[u8]) -> Self {
+        let byte = |at: usize| value.get(at).copied().unwrap_or(0);
+        Conditions::new(
+            ConditionFlags::from_bits_truncate(u16::from_le_bytes([byte(0), byte(1)])),
+            byte(2),
+        )
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_conditions_tests {
+    use super::*;
+
+    #[test]
+    fn conditions_add_and_remove() {
+        let mut conditions = Conditions::default();
+        conditions.add(&Conditions::new(
+            ConditionFlags::POISONED | ConditionFlags::PRONE,
+            5,
+        ));
+        conditions.add(&Conditions::new(ConditionFlags::empty(), 3));
+        assert_eq!(
+            conditions,
+            Conditions::new(ConditionFlags::POISONED | ConditionFlags::PRONE, 6)
+        );
+
+        conditions.remove(&Conditions::new(ConditionFlags::PRONE, 2));
+        assert_eq!(conditions, Conditions::new(ConditionFlags::POISONED, 4));
+    }
+
+    #[test]
+    fn conditions_bytes_round_trip() {
+        let conditions = Conditions::new(ConditionFlags::STUNNED | ConditionFlags::BLINDED, 2);
+        assert_eq!(conditions.to_bytes(), vec![0x01, 0x10, 0x02]);
+        assert_eq!(Conditions::from(&conditions.to_bytes()[..]), conditions);
+    }
+}
@@ -1,4 +1,5 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HealthPoints {
     pub current: u8,
     pub max: u8,
@@ -6,9 +7,10 @@ pub struct HealthPoints {
 
 impl From<&[u8]> for HealthPoints {
     fn from(value: &[u8]) -> Self {
+        let byte = |at: usize| value.get(at).copied().unwrap_or(0);
         HealthPoints {
-            current: value[0],
-            max: value[1],
+            current: byte(0),
+            max: byte(1),
         }
     }
 }
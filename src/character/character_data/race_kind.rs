@@ -1,4 +1,10 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(remote = "Self")
+)]
+#[repr(u8)]
 pub enum RaceKind {
     DWARF = 1,
     ELF = 2,
@@ -9,6 +15,8 @@ pub enum RaceKind {
     HUMAN = 7,
     ORC = 8,
     TIEFLING = 9,
+    /// A race this build doesn't know, e.g. homebrew or from a newer peer, kept as its byte.
+    Unknown(u8),
 }
 
 impl From<&[u8]> for RaceKind {
@@ -23,13 +31,30 @@ impl From<&[u8]> for RaceKind {
             Some(7) => RaceKind::HUMAN,
             Some(8) => RaceKind::ORC,
             Some(9) => RaceKind::TIEFLING,
-            _ => unimplemented!("No other races currently"),
+            Some(id) => RaceKind::Unknown(*id),
+            None => RaceKind::Unknown(0),
         }
     }
 }
 
 impl RaceKind {
+    /// Every built in race, i.e. all but `Unknown`.
+    pub const ALL: [RaceKind; 9] = [
+        RaceKind::DWARF,
+        RaceKind::ELF,
+        RaceKind::GNOME,
+        RaceKind::HALFELF,
+        RaceKind::HALFLING,
+        RaceKind::HALFORC,
+        RaceKind::HUMAN,
+        RaceKind::ORC,
+        RaceKind::TIEFLING,
+    ];
+
     pub fn discriminant(&self) -> u8 {
-        unsafe { *(self as *const Self as *const u8) }
+        match self {
+            RaceKind::Unknown(id) => *id,
+            _ => unsafe { *(self as *const Self as *const u8) },
+        }
     }
 }
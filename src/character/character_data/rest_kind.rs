@@ -0,0 +1,29 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(remote = "Self")
+)]
+pub enum RestKind {
+    SHORT = 1,
+    LONG = 2,
+}
+
+/// Fails with the byte when no rest uses it.
+impl TryFrom<u8> for RestKind {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(RestKind::SHORT),
+            2 => Ok(RestKind::LONG),
+            other => Err(other),
+        }
+    }
+}
+
+impl RestKind {
+    pub fn discriminant(&self) -> u8 {
+        unsafe { *(self as *const Self as *const u8) }
+    }
+}
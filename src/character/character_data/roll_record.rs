@@ -0,0 +1,68 @@
+use crate::dice::{DieResult, RollResult};
+
+/// A server made roll: the expression asked for and every die that was rolled for it.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RollRecord {
+    pub expression: String,
+    pub result: RollResult,
+}
+
+/// A roll in the session log along with who asked for it.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RollLogEntry {
+    pub roller: String,
+    pub record: RollRecord,
+}
+
+impl RollRecord {
+    pub const DIE_SIZE: u16 = 5;
+
+    /// Bytes the record takes up on the wire, or `None` when it has more dice than the count
+    /// byte holds or is longer than a data size can hold.
+    pub fn size(&self) -> Option<u16> {
+        let dice = u8::try_from(self.result.dice.len()).ok()?;
+        u16::try_from(self.expression.len())
+            .ok()?
+            .checked_add(1 + 4 + 4 + 1)?
+            .checked_add(u16::from(dice) * Self::DIE_SIZE)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = [self.expression.as_bytes(), b"\x00"].concat();
+        bytes.extend(self.result.total.to_le_bytes());
+        bytes.extend(self.result.modifier.to_le_bytes());
+        bytes.push(self.result.dice.len() as u8);
+        for die in &self.result.dice {
+            bytes.extend((die.sides as u16).to_le_bytes());
+            bytes.extend((die.value as u16).to_le_bytes());
+            bytes.push(die.kept.into());
+        }
+        bytes
+    }
+}
+
+impl RollLogEntry {
+    pub fn size(&self) -> Option<u16> {
+        u16::try_from(self.roller.len())
+            .ok()?
+            .checked_add(1)?
+            .checked_add(self.record.size()?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.roller.as_bytes(), b"\x00", &self.record.to_bytes()].concat()
+    }
+}
+
+impl From<&[u8]> for DieResult {
+    fn from(value: &[u8]) -> Self {
+        let byte = |at: usize| value.get(at).copied().unwrap_or(0);
+        DieResult {
+            sides: u16::from_le_bytes([byte(0), byte(1)]).into(),
+            value: u16::from_le_bytes([byte(2), byte(3)]).into(),
+            kept: byte(4) != 0,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use bitflags::bitflags;
+
+bitflags! {
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct SkillFlags: u32 {
+        const ACROBATICS = 1 << 0;
+        const ANIMALHANDLING = 1 << 1;
+        const ARCANA = 1 << 2;
+        const ATHLETICS = 1 << 3;
+        const DECEPTION = 1 << 4;
+        const HISTORY = 1 << 5;
+        const INSIGHT = 1 << 6;
+        const INTIMIDATION = 1 << 7;
+        const INVESTIGATION = 1 << 8;
+        const MEDICINE = 1 << 9;
+        const NATURE = 1 << 10;
+        const PERCEPTION = 1 << 11;
+        const PERFORMANCE = 1 << 12;
+        const PERSUASION = 1 << 13;
+        const RELIGION = 1 << 14;
+        const SLEIGHTOFHAND = 1 << 15;
+        const STEALTH = 1 << 16;
+        const SURVIVAL = 1 << 17;
+    }
+}
+
+/// Which skills a character is proficient in, and which of those they have expertise in.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SkillProficiencies {
+    pub proficient: SkillFlags,
+    pub expertise: SkillFlags,
+}
+
+impl SkillProficiencies {
+    pub const SIZE: u16 = 6;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proficient = self.proficient.bits().to_le_bytes();
+        let expertise = self.expertise.bits().to_le_bytes();
+        [&proficient[..3], &expertise[..3]].concat()
+    }
+}
+
+impl From<&[u8]> for SkillProficiencies {
+    fn from(value: &[u8]) -> Self {
+        let byte = |at: usize| value.get(at).copied().unwrap_or(0);
+        SkillProficiencies {
+            proficient: SkillFlags::from_bits_truncate(u32::from_le_bytes([
+                byte(0),
+                byte(1),
+                byte(2),
+                0,
+            ])),
+            expertise: SkillFlags::from_bits_truncate(u32::from_le_bytes([
+                byte(3),
+                byte(4),
+                byte(5),
+                0,
+            ])),
+        }
+    }
+}
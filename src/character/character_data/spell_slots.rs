@@ -0,0 +1,47 @@
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpellSlots {
+    pub max: [u8; 9],
+    pub used: [u8; 9],
+    pub pact: PactSlots,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PactSlots {
+    pub level: u8,
+    pub max: u8,
+    pub used: u8,
+}
+
+impl SpellSlots {
+    pub const SIZE: u16 = 21;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self
+            .max
+            .iter()
+            .zip(self.used.iter())
+            .flat_map(|(max, used)| [*max, *used])
+            .collect();
+        bytes.extend([self.pact.level, self.pact.max, self.pact.used]);
+        bytes
+    }
+}
+
+impl From<&[u8]> for SpellSlots {
+    fn from(value: &[u8]) -> Self {
+        let byte = |at: usize| value.get(at).copied().unwrap_or(0);
+        let mut slots = SpellSlots::default();
+        for level in 0..9 {
+            slots.max[level] = byte(level * 2);
+            slots.used[level] = byte(level * 2 + 1);
+        }
+        slots.pact = PactSlots {
+            level: byte(18),
+            max: byte(19),
+            used: byte(20),
+        };
+        slots
+    }
+}
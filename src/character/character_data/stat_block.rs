@@ -1,4 +1,27 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ability {
+    STRENGTH,
+    DEXTERITY,
+    CONSTITUTION,
+    INTELLIGENCE,
+    WISDOM,
+    CHARISMA,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 6] = [
+        Ability::STRENGTH,
+        Ability::DEXTERITY,
+        Ability::CONSTITUTION,
+        Ability::INTELLIGENCE,
+        Ability::WISDOM,
+        Ability::CHARISMA,
+    ];
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StatBlock {
     pub strength: u8,
     pub dexterity: u8,
@@ -8,15 +31,17 @@ pub struct StatBlock {
     pub charisma: u8,
 }
 
+/// Scores missing from a short slice read as zero.
 impl From<&[u8]> for StatBlock {
     fn from(value: &[u8]) -> Self {
+        let byte = |at: usize| value.get(at).copied().unwrap_or(0);
         StatBlock {
-            strength: value[0],
-            dexterity: value[1],
-            constitution: value[2],
-            intelligence: value[3],
-            wisdom: value[4],
-            charisma: value[5],
+            strength: byte(0),
+            dexterity: byte(1),
+            constitution: byte(2),
+            intelligence: byte(3),
+            wisdom: byte(4),
+            charisma: byte(5),
         }
     }
 }
@@ -32,4 +57,54 @@ impl StatBlock {
             charisma: chr,
         }
     }
+
+    pub fn score(&self, ability: Ability) -> u8 {
+        match ability {
+            Ability::STRENGTH => self.strength,
+            Ability::DEXTERITY => self.dexterity,
+            Ability::CONSTITUTION => self.constitution,
+            Ability::INTELLIGENCE => self.intelligence,
+            Ability::WISDOM => self.wisdom,
+            Ability::CHARISMA => self.charisma,
+        }
+    }
+
+    pub fn set_score(&mut self, ability: Ability, score: u8) {
+        match ability {
+            Ability::STRENGTH => self.strength = score,
+            Ability::DEXTERITY => self.dexterity = score,
+            Ability::CONSTITUTION => self.constitution = score,
+            Ability::INTELLIGENCE => self.intelligence = score,
+            Ability::WISDOM => self.wisdom = score,
+            Ability::CHARISMA => self.charisma = score,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        Ability::ALL
+            .iter()
+            .map(|ability| self.score(*ability))
+            .collect()
+    }
+
+    /// Ability modifier, i.e. (score - 10) / 2 rounded down.
+    pub fn modifier(&self, ability: Ability) -> i8 {
+        (i16::from(self.score(ability)) - 10).div_euclid(2) as i8
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_stat_block_tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_round_down() {
+        let stats = StatBlock::new(8, 9, 10, 11, 1, 30);
+        assert_eq!(stats.modifier(Ability::STRENGTH), -1);
+        assert_eq!(stats.modifier(Ability::DEXTERITY), -1);
+        assert_eq!(stats.modifier(Ability::CONSTITUTION), 0);
+        assert_eq!(stats.modifier(Ability::INTELLIGENCE), 0);
+        assert_eq!(stats.modifier(Ability::WISDOM), -5);
+        assert_eq!(stats.modifier(Ability::CHARISMA), 10);
+    }
 }
@@ -0,0 +1,168 @@
+use super::character_data::{
+    class_type::ClassType,
+    combat_stats::CombatStats,
+    race_kind::RaceKind,
+    stat_block::{Ability, StatBlock},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArmorKind {
+    PADDED,
+    LEATHER,
+    STUDDEDLEATHER,
+    HIDE,
+    CHAINSHIRT,
+    SCALEMAIL,
+    BREASTPLATE,
+    HALFPLATE,
+    RINGMAIL,
+    CHAINMAIL,
+    SPLINT,
+    PLATE,
+}
+
+impl ArmorKind {
+    pub fn base_armor_class(&self) -> u8 {
+        match self {
+            ArmorKind::PADDED | ArmorKind::LEATHER => 11,
+            ArmorKind::STUDDEDLEATHER | ArmorKind::HIDE => 12,
+            ArmorKind::CHAINSHIRT => 13,
+            ArmorKind::SCALEMAIL | ArmorKind::BREASTPLATE | ArmorKind::RINGMAIL => 14,
+            ArmorKind::HALFPLATE => 15,
+            ArmorKind::CHAINMAIL => 16,
+            ArmorKind::SPLINT => 17,
+            ArmorKind::PLATE => 18,
+        }
+    }
+
+    /// Most dexterity modifier the armor allows: unlimited for light, 2 for medium, none for heavy.
+    pub fn dexterity_cap(&self) -> Option<i8> {
+        match self {
+            ArmorKind::PADDED | ArmorKind::LEATHER | ArmorKind::STUDDEDLEATHER => None,
+            ArmorKind::HIDE
+            | ArmorKind::CHAINSHIRT
+            | ArmorKind::SCALEMAIL
+            | ArmorKind::BREASTPLATE
+            | ArmorKind::HALFPLATE => Some(2),
+            ArmorKind::RINGMAIL | ArmorKind::CHAINMAIL | ArmorKind::SPLINT | ArmorKind::PLATE => {
+                Some(0)
+            }
+        }
+    }
+}
+
+/// Armor and shield a character has equipped.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Equipment {
+    pub armor: Option<ArmorKind>,
+    pub shield: bool,
+}
+
+pub fn initiative(stats: &StatBlock) -> i8 {
+    stats.modifier(Ability::DEXTERITY)
+}
+
+/// Armor class from equipped armor, or unarmored defense for barbarians and monks.
+pub fn armor_class(class: &ClassType, stats: &StatBlock, equipment: &Equipment) -> u8 {
+    let dexterity = stats.modifier(Ability::DEXTERITY);
+    let shield = if equipment.shield { 2 } else { 0 };
+    let armor_class = match equipment.armor {
+        Some(armor) => {
+            let dexterity = match armor.dexterity_cap() {
+                None => dexterity,
+                Some(0) => 0,
+                Some(cap) => dexterity.min(cap),
+            };
+            i16::from(armor.base_armor_class()) + i16::from(dexterity) + shield
+        }
+        None => match class {
+            ClassType::BARBARIAN => {
+                10 + i16::from(dexterity)
+                    + i16::from(stats.modifier(Ability::CONSTITUTION))
+                    + shield
+            }
+            ClassType::MONK if !equipment.shield => {
+                10 + i16::from(dexterity) + i16::from(stats.modifier(Ability::WISDOM))
+            }
+            _ => 10 + i16::from(dexterity) + shield,
+        },
+    };
+    armor_class.clamp(0, u8::MAX.into()) as u8
+}
+
+pub fn combat_stats(
+    class: &ClassType,
+    race: &RaceKind,
+    stats: &StatBlock,
+    equipment: &Equipment,
+) -> CombatStats {
+    CombatStats {
+        armor_class: armor_class(class, stats, equipment),
+        initiative: initiative(stats),
+        speed: race.rules().speed,
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_derived_stats_tests {
+    use super::*;
+
+    #[test]
+    fn armor_caps_dexterity() {
+        let stats = StatBlock::new(10, 18, 10, 10, 10, 10);
+        let armored = |armor| Equipment {
+            armor: Some(armor),
+            shield: false,
+        };
+        assert_eq!(
+            armor_class(&ClassType::ROGUE, &stats, &armored(ArmorKind::LEATHER)),
+            15
+        );
+        assert_eq!(
+            armor_class(&ClassType::ROGUE, &stats, &armored(ArmorKind::HALFPLATE)),
+            17
+        );
+        assert_eq!(
+            armor_class(&ClassType::ROGUE, &stats, &armored(ArmorKind::PLATE)),
+            18
+        );
+    }
+
+    #[test]
+    fn unarmored_defense_by_class() {
+        let stats = StatBlock::new(10, 14, 16, 10, 18, 10);
+        let unarmored = Equipment::default();
+        let shield = Equipment {
+            armor: None,
+            shield: true,
+        };
+        assert_eq!(armor_class(&ClassType::WIZARD, &stats, &unarmored), 12);
+        assert_eq!(armor_class(&ClassType::BARBARIAN, &stats, &unarmored), 15);
+        assert_eq!(armor_class(&ClassType::BARBARIAN, &stats, &shield), 17);
+        assert_eq!(armor_class(&ClassType::MONK, &stats, &unarmored), 16);
+        assert_eq!(armor_class(&ClassType::MONK, &stats, &shield), 14);
+    }
+
+    #[test]
+    fn combat_stats_combine_race_and_dexterity() {
+        let stats = StatBlock::new(10, 8, 10, 10, 10, 10);
+        assert_eq!(
+            combat_stats(
+                &ClassType::FIGHTER,
+                &RaceKind::DWARF,
+                &stats,
+                &Equipment {
+                    armor: Some(ArmorKind::CHAINMAIL),
+                    shield: true
+                }
+            ),
+            CombatStats {
+                armor_class: 18,
+                initiative: -1,
+                speed: 25
+            }
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use super::{
+    character_data::{
+        class_type::ClassType,
+        stat_block::{Ability, StatBlock},
+    },
+    skills::proficiency_bonus,
+    spellcasting::{max_spell_slots, pact_slots},
+};
+
+pub const MAX_LEVEL: u8 = 20;
+
+/// Experience points needed to reach each level, starting at level 1.
+pub const XP_THRESHOLDS: [u32; 20] = [
+    0, 300, 900, 2700, 6500, 14000, 23000, 34000, 48000, 64000, 85000, 100000, 120000, 140000,
+    165000, 195000, 225000, 265000, 305000, 355000,
+];
+
+pub fn level_for_xp(xp: u32) -> u8 {
+    XP_THRESHOLDS
+        .iter()
+        .take_while(|threshold| **threshold <= xp)
+        .count() as u8
+}
+
+/// How the hit points for a new level are decided.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HpRoll {
+    AVERAGE,
+    ROLLED(u8),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Feature {
+    PROFICIENCYBONUS(i8),
+    ABILITYSCOREIMPROVEMENT,
+    EXTRAATTACK,
+    SPELLSLOTLEVEL(u8),
+    PACTSLOTLEVEL(u8),
+}
+
+/// What a single level gained.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LevelUp {
+    pub level: u8,
+    pub hp_gained: u8,
+    pub features: Vec<Feature>,
+}
+
+/// Hit points gained on reaching a level past 1: the hit die roll (or its rounded up average)
+/// plus the constitution modifier, never less than 1.
+pub fn hp_gain(class: &ClassType, stats: &StatBlock, roll: HpRoll) -> u8 {
+    let hit_die = class.rules().hit_die;
+    let die = match roll {
+        HpRoll::AVERAGE => hit_die / 2 + 1,
+        HpRoll::ROLLED(value) => value.clamp(1, hit_die),
+    };
+    (i16::from(die) + i16::from(stats.modifier(Ability::CONSTITUTION))).max(1) as u8
+}
+
+/// Features unlocked on reaching `level`.
+pub fn features(class: &ClassType, level: u8) -> Vec<Feature> {
+    let mut features = Vec::new();
+    if level > 1 && proficiency_bonus(level) != proficiency_bonus(level - 1) {
+        features.push(Feature::PROFICIENCYBONUS(proficiency_bonus(level)));
+    }
+    let improvement = match class {
+        ClassType::FIGHTER => matches!(level, 4 | 6 | 8 | 12 | 14 | 16 | 19),
+        ClassType::ROGUE => matches!(level, 4 | 8 | 10 | 12 | 16 | 19),
+        _ => matches!(level, 4 | 8 | 12 | 16 | 19),
+    };
+    if improvement {
+        features.push(Feature::ABILITYSCOREIMPROVEMENT);
+    }
+    if level == 5
+        && matches!(
+            class,
+            ClassType::BARBARIAN
+                | ClassType::BLOODHUNTER
+                | ClassType::FIGHTER
+                | ClassType::MONK
+                | ClassType::PALADIN
+                | ClassType::RANGER
+        )
+    {
+        features.push(Feature::EXTRAATTACK);
+    }
+    let highest_slot = |level: u8| {
+        max_spell_slots(class, level)
+            .iter()
+            .rposition(|slots| *slots > 0)
+            .map_or(0, |index| index as u8 + 1)
+    };
+    if highest_slot(level) > highest_slot(level.saturating_sub(1)) {
+        features.push(Feature::SPELLSLOTLEVEL(highest_slot(level)));
+    }
+    let pact_level = pact_slots(class, level).level;
+    if pact_level > pact_slots(class, level.saturating_sub(1)).level {
+        features.push(Feature::PACTSLOTLEVEL(pact_level));
+    }
+    features
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_experience_tests {
+    use super::*;
+
+    #[test]
+    fn level_for_xp_uses_thresholds() {
+        assert_eq!(level_for_xp(0), 1);
+        assert_eq!(level_for_xp(299), 1);
+        assert_eq!(level_for_xp(300), 2);
+        assert_eq!(level_for_xp(6500), 5);
+        assert_eq!(level_for_xp(u32::MAX), 20);
+    }
+
+    #[test]
+    fn hp_gain_adds_constitution() {
+        let stats = StatBlock::new(10, 10, 14, 10, 10, 10);
+        assert_eq!(hp_gain(&ClassType::FIGHTER, &stats, HpRoll::AVERAGE), 8);
+        assert_eq!(hp_gain(&ClassType::WIZARD, &stats, HpRoll::ROLLED(1)), 3);
+        assert_eq!(hp_gain(&ClassType::WIZARD, &stats, HpRoll::ROLLED(12)), 8);
+
+        let frail = StatBlock::new(10, 10, 3, 10, 10, 10);
+        assert_eq!(hp_gain(&ClassType::WIZARD, &frail, HpRoll::ROLLED(1)), 1);
+    }
+
+    #[test]
+    fn features_by_level() {
+        assert_eq!(
+            features(&ClassType::FIGHTER, 5),
+            vec![Feature::PROFICIENCYBONUS(3), Feature::EXTRAATTACK]
+        );
+        assert_eq!(
+            features(&ClassType::WIZARD, 4),
+            vec![Feature::ABILITYSCOREIMPROVEMENT]
+        );
+        assert_eq!(
+            features(&ClassType::WIZARD, 3),
+            vec![Feature::SPELLSLOTLEVEL(2)]
+        );
+        assert_eq!(
+            features(&ClassType::WARLOCK, 9),
+            vec![Feature::PROFICIENCYBONUS(4), Feature::PACTSLOTLEVEL(5)]
+        );
+    }
+}
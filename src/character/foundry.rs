@@ -0,0 +1,538 @@
+//! Import and export of Foundry VTT dnd5e actors, the JSON a character sheet is exported as from
+//! the virtual tabletop.
+
+use std::fmt;
+
+use serde_json::{json, Map, Value};
+
+use super::{
+    character_data::{
+        class_type::ClassType, health_points::HealthPoints, race_kind::RaceKind,
+        stat_block::StatBlock,
+    },
+    registry::Registry,
+    spellcasting::Spellcasting,
+    Character,
+};
+
+/// Foundry's keys for the six abilities, in `StatBlock` order.
+const ABILITY_KEYS: [&str; 6] = ["str", "dex", "con", "int", "wis", "cha"];
+
+#[derive(Debug, PartialEq)]
+pub enum FoundryError {
+    Json(String),
+    Missing(String),
+    Invalid(String),
+    UnknownClass(String),
+    UnknownRace(String),
+}
+
+impl fmt::Display for FoundryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FoundryError::Json(e) => write!(f, "Unable to parse actor: {}", e),
+            FoundryError::Missing(path) => write!(f, "Actor has no {}", path),
+            FoundryError::Invalid(path) => write!(f, "Actor {} is not a valid value", path),
+            FoundryError::UnknownClass(name) => write!(f, "No class named {}", name),
+            FoundryError::UnknownRace(name) => write!(f, "No race named {}", name),
+        }
+    }
+}
+
+impl std::error::Error for FoundryError {}
+
+/// An imported character and the actor fields, as JSON paths, that had nowhere to go.
+#[derive(Debug, PartialEq)]
+pub struct FoundryImport {
+    pub character: Character,
+    pub unmapped: Vec<String>,
+}
+
+/// An exported actor and the character fields the actor has nowhere to keep.
+#[derive(Debug, PartialEq)]
+pub struct FoundryExport {
+    pub actor: Value,
+    pub unmapped: Vec<String>,
+}
+
+fn class_label(class: &ClassType) -> String {
+    match class {
+        ClassType::ARTIFICER => "Artificer",
+        ClassType::BARBARIAN => "Barbarian",
+        ClassType::BARD => "Bard",
+        ClassType::BLOODHUNTER => "Blood Hunter",
+        ClassType::CLERIC => "Cleric",
+        ClassType::DRUID => "Druid",
+        ClassType::FIGHTER => "Fighter",
+        ClassType::MONK => "Monk",
+        ClassType::PALADIN => "Paladin",
+        ClassType::RANGER => "Ranger",
+        ClassType::ROGUE => "Rogue",
+        ClassType::SORCERER => "Sorcerer",
+        ClassType::WARLOCK => "Warlock",
+        ClassType::WIZARD => "Wizard",
+        ClassType::Unknown(_) => return class.name(),
+    }
+    .to_string()
+}
+
+fn race_label(race: &RaceKind) -> String {
+    match race {
+        RaceKind::DWARF => "Dwarf",
+        RaceKind::ELF => "Elf",
+        RaceKind::GNOME => "Gnome",
+        RaceKind::HALFELF => "Half-Elf",
+        RaceKind::HALFLING => "Halfling",
+        RaceKind::HALFORC => "Half-Orc",
+        RaceKind::HUMAN => "Human",
+        RaceKind::ORC => "Orc",
+        RaceKind::TIEFLING => "Tiefling",
+        RaceKind::Unknown(_) => return race.name(),
+    }
+    .to_string()
+}
+
+/// Foundry's item identifier for a label, e.g. `Blood Hunter` becomes `blood-hunter`.
+fn identifier(label: &str) -> String {
+    label.to_lowercase().replace(' ', "-")
+}
+
+/// Uppercase letters and digits only, so `Half-Elf`, `half-elf` and `HALFELF` all compare equal.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn class_named(name: &str) -> Option<ClassType> {
+    let name = normalize(name);
+    ClassType::ALL
+        .into_iter()
+        .find(|class| normalize(&format!("{:?}", class)) == name)
+        .or_else(|| {
+            Registry::installed()
+                .classes
+                .iter()
+                .find(|class| normalize(&class.name) == name)
+                .map(|class| ClassType::Unknown(class.id))
+        })
+}
+
+fn race_named(name: &str) -> Option<RaceKind> {
+    let name = normalize(name);
+    RaceKind::ALL
+        .into_iter()
+        .find(|race| normalize(&format!("{:?}", race)) == name)
+        .or_else(|| {
+            Registry::installed()
+                .races
+                .iter()
+                .find(|race| normalize(&race.name) == name)
+                .map(|race| RaceKind::Unknown(race.id))
+        })
+}
+
+fn pointer<'a>(actor: &'a Value, path: &str) -> Option<&'a Value> {
+    actor.pointer(&format!("/{}", path.replace('.', "/")))
+}
+
+fn required_u8(actor: &Value, path: &str) -> Result<u8, FoundryError> {
+    pointer(actor, path)
+        .ok_or_else(|| FoundryError::Missing(path.to_string()))?
+        .as_u64()
+        .and_then(|value| u8::try_from(value).ok())
+        .ok_or_else(|| FoundryError::Invalid(path.to_string()))
+}
+
+/// Leading number of a free text age like `30 years`.
+fn parse_age(age: &Value) -> Option<u16> {
+    match age {
+        Value::Number(age) => age.as_u64().and_then(|age| u16::try_from(age).ok()),
+        Value::String(age) => {
+            let digits: String = age
+                .trim()
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    text.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(values) => values.is_empty(),
+        Value::Object(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+/// Collects the paths of non-empty values that neither are nor contain a `mapped` path.
+fn unmapped_paths(value: &Value, path: &str, mapped: &[String], unmapped: &mut Vec<String>) {
+    if mapped.iter().any(|mapped| mapped == path) {
+        return;
+    }
+    let inside = |mapped: &String| {
+        path.is_empty()
+            || mapped.starts_with(&format!("{}.", path))
+            || mapped.starts_with(&format!("{}[", path))
+    };
+    if !mapped.iter().any(inside) {
+        if !is_empty(value) {
+            unmapped.push(path.to_string());
+        }
+        return;
+    }
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        Value::Object(values) => {
+            for (key, value) in values {
+                unmapped_paths(value, &join(key), mapped, unmapped);
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                unmapped_paths(value, &format!("{}[{}]", path, index), mapped, unmapped);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Reads a dnd5e actor. With several class items the one with the most levels becomes the class
+/// and the character level is the sum of them all.
+pub fn import_actor(actor: &str) -> Result<FoundryImport, FoundryError> {
+    let actor: Value =
+        serde_json::from_str(actor).map_err(|e| FoundryError::Json(e.to_string()))?;
+    let mut mapped: Vec<String> = vec!["name".to_string(), "type".to_string()];
+
+    let name = actor
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| FoundryError::Missing("name".to_string()))?
+        .to_string();
+
+    let mut scores = [0u8; 6];
+    for (score, key) in scores.iter_mut().zip(ABILITY_KEYS) {
+        let path = format!("system.abilities.{}.value", key);
+        *score = required_u8(&actor, &path)?;
+        mapped.push(path);
+    }
+    let stats = StatBlock::from(&scores[..]);
+
+    let current = required_u8(&actor, "system.attributes.hp.value")?;
+    let max = match pointer(&actor, "system.attributes.hp.max") {
+        None | Some(Value::Null) => current,
+        Some(_) => required_u8(&actor, "system.attributes.hp.max")?,
+    };
+    mapped.push("system.attributes.hp.value".to_string());
+    mapped.push("system.attributes.hp.max".to_string());
+
+    let items = actor
+        .get("items")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let item_name = |item: &Value| {
+        item.pointer("/system/identifier")
+            .or_else(|| item.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let mut classes: Vec<(usize, u8)> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.get("type").and_then(Value::as_str) == Some("class"))
+        .map(|(index, item)| {
+            let levels = item
+                .pointer("/system/levels")
+                .and_then(Value::as_u64)
+                .unwrap_or(1);
+            (index, levels.min(u64::from(u8::MAX)) as u8)
+        })
+        .collect();
+    classes.sort_by_key(|(_, levels)| std::cmp::Reverse(*levels));
+    let (class_index, _) = *classes
+        .first()
+        .ok_or_else(|| FoundryError::Missing("class item".to_string()))?;
+    let class_name = item_name(&items[class_index]);
+    let class = class_named(&class_name).ok_or(FoundryError::UnknownClass(class_name))?;
+    mapped.push(format!("items[{}]", class_index));
+    let level = classes
+        .iter()
+        .fold(0u8, |level, (_, levels)| level.saturating_add(*levels))
+        .clamp(1, 20);
+
+    let race_item = items
+        .iter()
+        .position(|item| item.get("type").and_then(Value::as_str) == Some("race"));
+    let race_name = match race_item {
+        Some(index) => {
+            mapped.push(format!("items[{}]", index));
+            item_name(&items[index])
+        }
+        None => pointer(&actor, "system.details.race")
+            .and_then(Value::as_str)
+            .ok_or_else(|| FoundryError::Missing("system.details.race".to_string()))?
+            .to_string(),
+    };
+    let race = race_named(&race_name).ok_or(FoundryError::UnknownRace(race_name))?;
+    mapped.push("system.details.race".to_string());
+
+    let age = pointer(&actor, "system.details.age").and_then(parse_age);
+    if age.is_some() {
+        mapped.push("system.details.age".to_string());
+    }
+
+    let description = pointer(&actor, "system.details.biography.value")
+        .and_then(Value::as_str)
+        .map(strip_html)
+        .unwrap_or_default();
+    mapped.push("system.details.biography.value".to_string());
+
+    let mut character = Character::new(
+        name,
+        race,
+        class,
+        level,
+        stats,
+        HealthPoints { current, max },
+        age.unwrap_or_default(),
+        description,
+    );
+    if let Some(xp) = pointer(&actor, "system.details.xp.value").and_then(Value::as_u64) {
+        character.xp = xp.min(u64::from(u32::MAX)) as u32;
+        mapped.push("system.details.xp.value".to_string());
+    }
+
+    let mut unmapped = Vec::new();
+    unmapped_paths(&actor, "", &mapped, &mut unmapped);
+    Ok(FoundryImport {
+        character,
+        unmapped,
+    })
+}
+
+/// Writes a dnd5e actor with a class item and a race item.
+pub fn export_actor(character: &Character) -> FoundryExport {
+    let abilities: Map<String, Value> = ABILITY_KEYS
+        .iter()
+        .zip(super::character_data::stat_block::Ability::ALL)
+        .map(|(key, ability)| {
+            (
+                key.to_string(),
+                json!({ "value": character.stats.score(ability) }),
+            )
+        })
+        .collect();
+    let class = class_label(&character.class);
+    let race = race_label(&character.race);
+    let biography = if character.description.is_empty() {
+        String::new()
+    } else {
+        format!("<p>{}</p>", escape_html(&character.description))
+    };
+    let actor = json!({
+        "name": character.name,
+        "type": "character",
+        "system": {
+            "abilities": abilities,
+            "attributes": {
+                "hp": { "value": character.hp.current, "max": character.hp.max }
+            },
+            "details": {
+                "race": race,
+                "age": character.age.to_string(),
+                "xp": { "value": character.xp },
+                "biography": { "value": biography }
+            }
+        },
+        "items": [
+            {
+                "name": class,
+                "type": "class",
+                "system": {
+                    "identifier": identifier(&class),
+                    "levels": character.level,
+                    "hitDice": format!("d{}", character.class.rules().hit_die)
+                }
+            },
+            {
+                "name": race,
+                "type": "race",
+                "system": { "identifier": identifier(&race) }
+            }
+        ]
+    });
+
+    let mut unmapped = Vec::new();
+    if character.spellcasting != Spellcasting::new(&character.class, character.level) {
+        unmapped.push("spellcasting".to_string());
+    }
+    if character.conditions != Default::default() {
+        unmapped.push("conditions".to_string());
+    }
+    if character.skills != Default::default() {
+        unmapped.push("skills".to_string());
+    }
+    if character.equipment != Default::default() {
+        unmapped.push("equipment".to_string());
+    }
+    FoundryExport { actor, unmapped }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_foundry_tests {
+    use super::*;
+    use crate::character::character_data::conditions::{ConditionFlags, Conditions};
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!("fixtures/foundry/{}.json", name)).unwrap()
+    }
+
+    #[test]
+    fn imports_single_class_actor() {
+        let import = import_actor(&fixture("bart")).unwrap();
+        let mut bart = Character::new(
+            "Bart".to_string(),
+            RaceKind::HUMAN,
+            ClassType::FIGHTER,
+            5,
+            StatBlock::new(16, 12, 14, 10, 10, 8),
+            HealthPoints {
+                current: 34,
+                max: 42,
+            },
+            30,
+            "Retired caravan guard.".to_string(),
+        );
+        bart.xp = 7000;
+        assert_eq!(import.character, bart);
+        assert_eq!(
+            import.unmapped,
+            vec![
+                "img",
+                "items[1]",
+                "system.abilities.cha.proficient",
+                "system.abilities.con.proficient",
+                "system.abilities.dex.proficient",
+                "system.abilities.int.proficient",
+                "system.abilities.str.proficient",
+                "system.abilities.wis.proficient",
+                "system.attributes.ac",
+                "system.attributes.death",
+                "system.attributes.hp.temp",
+                "system.attributes.hp.tempmax",
+                "system.currency",
+                "system.details.alignment",
+                "system.details.background",
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_multiclass_actor_with_race_item() {
+        let import = import_actor(&fixture("lisa")).unwrap();
+        assert_eq!(import.character.race, RaceKind::HALFELF);
+        assert_eq!(import.character.class, ClassType::BARD);
+        assert_eq!(import.character.level, 4);
+        assert_eq!(
+            import.character.hp,
+            HealthPoints {
+                current: 27,
+                max: 27
+            }
+        );
+        assert_eq!(import.character.age, 0);
+        assert_eq!(import.unmapped, vec!["items[2]", "system.details.age"]);
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let mut bart = import_actor(&fixture("bart")).unwrap().character;
+        let export = export_actor(&bart);
+        assert_eq!(export.unmapped, Vec::<String>::new());
+        assert_eq!(export.actor["items"][0]["system"]["hitDice"], "d10");
+        let import = import_actor(&export.actor.to_string()).unwrap();
+        assert_eq!(import.character, bart);
+        assert_eq!(import.unmapped, Vec::<String>::new());
+
+        bart.conditions = Conditions::new(ConditionFlags::PRONE, 0);
+        assert_eq!(export_actor(&bart).unmapped, vec!["conditions"]);
+    }
+
+    #[test]
+    fn descriptions_escape_html() {
+        let mut bart = import_actor(&fixture("bart")).unwrap().character;
+        bart.description = "HP < 10 & falling".to_string();
+        let export = export_actor(&bart);
+        assert_eq!(
+            export.actor["system"]["details"]["biography"]["value"],
+            "<p>HP &lt; 10 &amp; falling</p>"
+        );
+        let import = import_actor(&export.actor.to_string()).unwrap();
+        assert_eq!(import.character.description, "HP < 10 & falling");
+
+        let authored = fixture("bart").replace(
+            "<p>Retired caravan guard.</p>",
+            "<p>Salt &amp; pepper &lt;beard&gt;</p>",
+        );
+        assert_eq!(
+            import_actor(&authored).unwrap().character.description,
+            "Salt & pepper <beard>"
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        assert_eq!(
+            import_actor(r#"{"name": "Nobody", "system": {}}"#),
+            Err(FoundryError::Missing(
+                "system.abilities.str.value".to_string()
+            ))
+        );
+        let gunslinger = fixture("bart").replace("\"fighter\"", "\"gunslinger\"");
+        assert_eq!(
+            import_actor(&gunslinger),
+            Err(FoundryError::UnknownClass("gunslinger".to_string()))
+        );
+    }
+}
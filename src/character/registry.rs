@@ -0,0 +1,292 @@
+use std::{fmt, sync::RwLock};
+
+use super::{
+    character_data::{class_type::ClassType, race_kind::RaceKind},
+    rules::{ClassRules, RaceRules},
+};
+
+/// A class the built in [`ClassType`] variants don't cover, decoded as `ClassType::Unknown(id)`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomClass {
+    pub id: u8,
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub rules: ClassRules,
+}
+
+/// A race the built in [`RaceKind`] variants don't cover, decoded as `RaceKind::Unknown(id)`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomRace {
+    pub id: u8,
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub rules: RaceRules,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RegistryError {
+    Io(String),
+    Parse(String),
+    UnsupportedFormat(String),
+    BuiltInClass(u8),
+    BuiltInRace(u8),
+    DuplicateClass(u8),
+    DuplicateRace(u8),
+    /// A class with a `hit_die` of 0.
+    NoHitDie(u8),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io(e) => write!(f, "Unable to read registry: {}", e),
+            RegistryError::Parse(e) => write!(f, "Unable to parse registry: {}", e),
+            RegistryError::UnsupportedFormat(path) => {
+                write!(f, "{} is not a .toml or .json registry", path)
+            }
+            RegistryError::BuiltInClass(id) => {
+                write!(f, "Class {} is built in and can not be redefined", id)
+            }
+            RegistryError::BuiltInRace(id) => {
+                write!(f, "Race {} is built in and can not be redefined", id)
+            }
+            RegistryError::DuplicateClass(id) => write!(f, "Class {} is defined twice", id),
+            RegistryError::DuplicateRace(id) => write!(f, "Race {} is defined twice", id),
+            RegistryError::NoHitDie(id) => write!(f, "Class {} needs a hit die of at least 1", id),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Names and rules data for custom class and race IDs.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Registry {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub classes: Vec<CustomClass>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub races: Vec<CustomRace>,
+}
+
+static INSTALLED: RwLock<Registry> = RwLock::new(Registry {
+    classes: Vec::new(),
+    races: Vec::new(),
+});
+
+impl Registry {
+    /// Errors if an entry tries to take over an ID that already has a built in variant or another
+    /// entry, or a class has no hit die.
+    pub fn validate(&self) -> Result<(), RegistryError> {
+        for (index, class) in self.classes.iter().enumerate() {
+            if ClassType::from(&[class.id][..]) != ClassType::Unknown(class.id) {
+                return Err(RegistryError::BuiltInClass(class.id));
+            }
+            if self.classes[..index]
+                .iter()
+                .any(|other| other.id == class.id)
+            {
+                return Err(RegistryError::DuplicateClass(class.id));
+            }
+            if class.rules.hit_die == 0 {
+                return Err(RegistryError::NoHitDie(class.id));
+            }
+        }
+        for (index, race) in self.races.iter().enumerate() {
+            if RaceKind::from(&[race.id][..]) != RaceKind::Unknown(race.id) {
+                return Err(RegistryError::BuiltInRace(race.id));
+            }
+            if self.races[..index].iter().any(|other| other.id == race.id) {
+                return Err(RegistryError::DuplicateRace(race.id));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn class(&self, id: u8) -> Option<&CustomClass> {
+        self.classes.iter().find(|class| class.id == id)
+    }
+
+    pub fn race(&self, id: u8) -> Option<&CustomRace> {
+        self.races.iter().find(|race| race.id == id)
+    }
+
+    /// Makes this the registry `ClassType::Unknown` and `RaceKind::Unknown` look their rules and
+    /// names up in.
+    pub fn install(self) -> Result<(), RegistryError> {
+        self.validate()?;
+        *INSTALLED.write().unwrap_or_else(|e| e.into_inner()) = self;
+        Ok(())
+    }
+
+    /// Copy of the installed registry.
+    pub fn installed() -> Registry {
+        INSTALLED.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    #[cfg(feature = "registry")]
+    pub fn from_toml(registry: &str) -> Result<Registry, RegistryError> {
+        let registry: Registry =
+            toml::from_str(registry).map_err(|e| RegistryError::Parse(e.to_string()))?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    #[cfg(feature = "registry")]
+    pub fn from_json(registry: &str) -> Result<Registry, RegistryError> {
+        let registry: Registry =
+            serde_json::from_str(registry).map_err(|e| RegistryError::Parse(e.to_string()))?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Reads a `.toml` or `.json` registry file.
+    #[cfg(feature = "registry")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Registry, RegistryError> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| RegistryError::Io(e.to_string()))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Registry::from_toml(&contents),
+            Some("json") => Registry::from_json(&contents),
+            _ => Err(RegistryError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+}
+
+pub(crate) fn custom_class_rules(id: u8) -> Option<ClassRules> {
+    let registry = INSTALLED.read().unwrap_or_else(|e| e.into_inner());
+    registry.class(id).map(|class| class.rules.clone())
+}
+
+pub(crate) fn custom_race_rules(id: u8) -> Option<RaceRules> {
+    let registry = INSTALLED.read().unwrap_or_else(|e| e.into_inner());
+    registry.race(id).map(|race| race.rules.clone())
+}
+
+impl ClassType {
+    /// Display name, taken from the installed registry for unknown classes.
+    pub fn name(&self) -> String {
+        match self {
+            ClassType::Unknown(id) => Registry::installed()
+                .class(*id)
+                .map_or_else(|| format!("Unknown({})", id), |class| class.name.clone()),
+            known => format!("{:?}", known),
+        }
+    }
+}
+
+impl RaceKind {
+    /// Display name, taken from the installed registry for unknown races.
+    pub fn name(&self) -> String {
+        match self {
+            RaceKind::Unknown(id) => Registry::installed()
+                .race(*id)
+                .map_or_else(|| format!("Unknown({})", id), |race| race.name.clone()),
+            known => format!("{:?}", known),
+        }
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_registry_tests {
+    use super::*;
+
+    #[test]
+    fn built_in_ids_can_not_be_redefined() {
+        let registry = Registry {
+            classes: vec![CustomClass {
+                id: 7,
+                name: "Not a fighter".to_string(),
+                rules: ClassRules::default(),
+            }],
+            races: vec![],
+        };
+        assert_eq!(registry.install(), Err(RegistryError::BuiltInClass(7)));
+    }
+
+    #[test]
+    fn classes_need_a_hit_die_and_ids_are_unique() {
+        let gunslinger = CustomClass {
+            id: 15,
+            name: "Gunslinger".to_string(),
+            rules: ClassRules::default(),
+        };
+        let registry = Registry {
+            classes: vec![gunslinger.clone(), gunslinger.clone()],
+            races: vec![],
+        };
+        assert_eq!(registry.validate(), Err(RegistryError::DuplicateClass(15)));
+
+        let mut no_hit_die = gunslinger;
+        no_hit_die.rules.hit_die = 0;
+        let registry = Registry {
+            classes: vec![no_hit_die],
+            races: vec![],
+        };
+        assert_eq!(registry.validate(), Err(RegistryError::NoHitDie(15)));
+
+        let aarakocra = CustomRace {
+            id: 10,
+            name: "Aarakocra".to_string(),
+            rules: RaceRules::default(),
+        };
+        let registry = Registry {
+            classes: vec![],
+            races: vec![aarakocra.clone(), aarakocra],
+        };
+        assert_eq!(registry.install(), Err(RegistryError::DuplicateRace(10)));
+    }
+
+    #[test]
+    fn unknown_ids_round_trip() {
+        assert_eq!(ClassType::from(&b"\x0F"[..]), ClassType::Unknown(15));
+        assert_eq!(ClassType::Unknown(15).discriminant(), 15);
+        assert_eq!(RaceKind::from(&b"\xF0"[..]), RaceKind::Unknown(240));
+        assert_eq!(RaceKind::Unknown(240).discriminant(), 240);
+        assert_eq!(ClassType::Unknown(200).rules(), ClassRules::default());
+        assert_eq!(ClassType::FIGHTER.name(), "FIGHTER");
+        assert_eq!(RaceKind::Unknown(201).name(), "Unknown(201)");
+    }
+
+    #[cfg(feature = "registry")]
+    #[test]
+    fn loads_toml_and_json_registries() {
+        use crate::character::{character_data::stat_block::Ability, rules::Size};
+
+        let toml = Registry::load("fixtures/homebrew.toml").unwrap();
+        let json = Registry::load("fixtures/homebrew.json").unwrap();
+        assert_eq!(toml, json);
+        assert_eq!(
+            toml.race(10),
+            Some(&CustomRace {
+                id: 10,
+                name: "Aarakocra".to_string(),
+                rules: RaceRules {
+                    ability_score_increases: vec![(Ability::DEXTERITY, 2), (Ability::WISDOM, 1)],
+                    speed: 25,
+                    size: Size::MEDIUM,
+                    darkvision: 0,
+                    lifespan: 30,
+                },
+            })
+        );
+        assert_eq!(
+            Registry::from_toml("[[races]]\nid = 4\nname = \"Elf\"\nability_score_increases = []\nspeed = 30\nsize = \"MEDIUM\"\ndarkvision = 60\nlifespan = 750\n"),
+            Err(RegistryError::BuiltInRace(4))
+        );
+
+        let gunslinger = toml.class(15).unwrap();
+        assert_eq!(gunslinger.name, "Gunslinger");
+        assert_eq!(gunslinger.rules.hit_die, 10);
+        assert_eq!(toml.race(10).unwrap().rules.lifespan, 30);
+
+        let duergar = Registry::from_toml("[[races]]\nid = 11\nname = \"Duergar\"\nability_score_increases = [[\"CONSTITUTION\", 2], [\"CHARISMA\", -1]]\nspeed = 25\nsize = \"MEDIUM\"\ndarkvision = 120\nlifespan = 350\n").unwrap();
+        assert_eq!(
+            duergar.race(11).unwrap().rules.ability_score_increases,
+            vec![(Ability::CONSTITUTION, 2), (Ability::CHARISMA, -1)]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use super::{
+    character_data::{class_type::ClassType, race_kind::RaceKind, stat_block::Ability},
+    registry::{custom_class_rules, custom_race_rules},
+};
+
+/// Game data for a class.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassRules {
+    pub hit_die: u8,
+    pub primary_abilities: Vec<Ability>,
+    pub saving_throws: Vec<Ability>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Size {
+    SMALL,
+    MEDIUM,
+}
+
+/// Game data for a race. Distances are in feet and lifespan in years.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RaceRules {
+    pub ability_score_increases: Vec<(Ability, i8)>,
+    pub speed: u8,
+    pub size: Size,
+    pub darkvision: u8,
+    pub lifespan: u16,
+}
+
+/// Rules for a class nobody registered: a d8 hit die and no proficiencies.
+impl Default for ClassRules {
+    fn default() -> Self {
+        ClassRules {
+            hit_die: 8,
+            primary_abilities: Vec::new(),
+            saving_throws: Vec::new(),
+        }
+    }
+}
+
+/// Rules for a race nobody registered: medium, 30 feet of speed and no limit on age.
+impl Default for RaceRules {
+    fn default() -> Self {
+        RaceRules {
+            ability_score_increases: Vec::new(),
+            speed: 30,
+            size: Size::MEDIUM,
+            darkvision: 0,
+            lifespan: u16::MAX,
+        }
+    }
+}
+
+impl ClassType {
+    /// Built in rules, or for unknown classes those of the installed registry.
+    pub fn rules(&self) -> ClassRules {
+        use Ability::*;
+        let (hit_die, primary_abilities, saving_throws) = match self {
+            ClassType::ARTIFICER => (8, vec![INTELLIGENCE], [CONSTITUTION, INTELLIGENCE]),
+            ClassType::BARBARIAN => (12, vec![STRENGTH], [STRENGTH, CONSTITUTION]),
+            ClassType::BARD => (8, vec![CHARISMA], [DEXTERITY, CHARISMA]),
+            ClassType::BLOODHUNTER => (
+                10,
+                vec![STRENGTH, DEXTERITY, INTELLIGENCE],
+                [DEXTERITY, INTELLIGENCE],
+            ),
+            ClassType::CLERIC => (8, vec![WISDOM], [WISDOM, CHARISMA]),
+            ClassType::DRUID => (8, vec![WISDOM], [INTELLIGENCE, WISDOM]),
+            ClassType::FIGHTER => (10, vec![STRENGTH, DEXTERITY], [STRENGTH, CONSTITUTION]),
+            ClassType::MONK => (8, vec![DEXTERITY, WISDOM], [STRENGTH, DEXTERITY]),
+            ClassType::PALADIN => (10, vec![STRENGTH, CHARISMA], [WISDOM, CHARISMA]),
+            ClassType::RANGER => (10, vec![DEXTERITY, WISDOM], [STRENGTH, DEXTERITY]),
+            ClassType::ROGUE => (8, vec![DEXTERITY], [DEXTERITY, INTELLIGENCE]),
+            ClassType::SORCERER => (6, vec![CHARISMA], [CONSTITUTION, CHARISMA]),
+            ClassType::WARLOCK => (8, vec![CHARISMA], [WISDOM, CHARISMA]),
+            ClassType::WIZARD => (6, vec![INTELLIGENCE], [INTELLIGENCE, WISDOM]),
+            ClassType::Unknown(id) => return custom_class_rules(*id).unwrap_or_default(),
+        };
+        ClassRules {
+            hit_die,
+            primary_abilities,
+            saving_throws: saving_throws.to_vec(),
+        }
+    }
+}
+
+impl RaceKind {
+    /// Built in rules, or for unknown races those of the installed registry.
+    pub fn rules(&self) -> RaceRules {
+        use Ability::*;
+        let (ability_score_increases, speed, size, darkvision, lifespan) = match self {
+            RaceKind::DWARF => (vec![(CONSTITUTION, 2)], 25, Size::MEDIUM, 60, 350),
+            RaceKind::ELF => (vec![(DEXTERITY, 2)], 30, Size::MEDIUM, 60, 750),
+            RaceKind::GNOME => (vec![(INTELLIGENCE, 2)], 25, Size::SMALL, 60, 500),
+            RaceKind::HALFELF => (vec![(CHARISMA, 2)], 30, Size::MEDIUM, 60, 200),
+            RaceKind::HALFLING => (vec![(DEXTERITY, 2)], 25, Size::SMALL, 0, 250),
+            RaceKind::HALFORC => (
+                vec![(STRENGTH, 2), (CONSTITUTION, 1)],
+                30,
+                Size::MEDIUM,
+                60,
+                75,
+            ),
+            RaceKind::HUMAN => (
+                Ability::ALL.map(|a| (a, 1)).to_vec(),
+                30,
+                Size::MEDIUM,
+                0,
+                100,
+            ),
+            RaceKind::ORC => (
+                vec![(STRENGTH, 2), (CONSTITUTION, 1), (INTELLIGENCE, -2)],
+                30,
+                Size::MEDIUM,
+                60,
+                50,
+            ),
+            RaceKind::TIEFLING => (
+                vec![(CHARISMA, 2), (INTELLIGENCE, 1)],
+                30,
+                Size::MEDIUM,
+                60,
+                110,
+            ),
+            RaceKind::Unknown(id) => return custom_race_rules(*id).unwrap_or_default(),
+        };
+        RaceRules {
+            ability_score_increases,
+            speed,
+            size,
+            darkvision,
+            lifespan,
+        }
+    }
+
+    /// Whether a character of this race could be `age` years old.
+    pub fn plausible_age(&self, age: u16) -> bool {
+        age <= self.rules().lifespan
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_rules_tests {
+    use super::*;
+
+    #[test]
+    fn class_rules() {
+        assert_eq!(
+            ClassType::PALADIN.rules(),
+            ClassRules {
+                hit_die: 10,
+                primary_abilities: vec![Ability::STRENGTH, Ability::CHARISMA],
+                saving_throws: vec![Ability::WISDOM, Ability::CHARISMA],
+            }
+        );
+        assert_eq!(ClassType::WIZARD.rules().hit_die, 6);
+        assert_eq!(ClassType::BARBARIAN.rules().hit_die, 12);
+    }
+
+    #[test]
+    fn race_rules() {
+        assert_eq!(
+            RaceKind::GNOME.rules(),
+            RaceRules {
+                ability_score_increases: vec![(Ability::INTELLIGENCE, 2)],
+                speed: 25,
+                size: Size::SMALL,
+                darkvision: 60,
+                lifespan: 500,
+            }
+        );
+        assert_eq!(RaceKind::HUMAN.rules().darkvision, 0);
+        let orc = RaceKind::ORC.rules();
+        let half_orc = RaceKind::HALFORC.rules();
+        assert_eq!(
+            orc.ability_score_increases,
+            vec![
+                (Ability::STRENGTH, 2),
+                (Ability::CONSTITUTION, 1),
+                (Ability::INTELLIGENCE, -2)
+            ]
+        );
+        assert_ne!(
+            orc.ability_score_increases,
+            half_orc.ability_score_increases
+        );
+        assert_ne!(orc.lifespan, half_orc.lifespan);
+        assert!(RaceKind::ELF.plausible_age(700));
+        assert!(!RaceKind::HUMAN.plausible_age(700));
+    }
+}
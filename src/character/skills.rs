@@ -0,0 +1,152 @@
+use super::character_data::{
+    skill_proficiencies::{SkillFlags, SkillProficiencies},
+    stat_block::{Ability, StatBlock},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Skill {
+    ACROBATICS,
+    ANIMALHANDLING,
+    ARCANA,
+    ATHLETICS,
+    DECEPTION,
+    HISTORY,
+    INSIGHT,
+    INTIMIDATION,
+    INVESTIGATION,
+    MEDICINE,
+    NATURE,
+    PERCEPTION,
+    PERFORMANCE,
+    PERSUASION,
+    RELIGION,
+    SLEIGHTOFHAND,
+    STEALTH,
+    SURVIVAL,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 18] = [
+        Skill::ACROBATICS,
+        Skill::ANIMALHANDLING,
+        Skill::ARCANA,
+        Skill::ATHLETICS,
+        Skill::DECEPTION,
+        Skill::HISTORY,
+        Skill::INSIGHT,
+        Skill::INTIMIDATION,
+        Skill::INVESTIGATION,
+        Skill::MEDICINE,
+        Skill::NATURE,
+        Skill::PERCEPTION,
+        Skill::PERFORMANCE,
+        Skill::PERSUASION,
+        Skill::RELIGION,
+        Skill::SLEIGHTOFHAND,
+        Skill::STEALTH,
+        Skill::SURVIVAL,
+    ];
+
+    /// The ability a skill check adds the modifier of.
+    pub fn ability(&self) -> Ability {
+        match self {
+            Skill::ATHLETICS => Ability::STRENGTH,
+            Skill::ACROBATICS | Skill::SLEIGHTOFHAND | Skill::STEALTH => Ability::DEXTERITY,
+            Skill::ARCANA
+            | Skill::HISTORY
+            | Skill::INVESTIGATION
+            | Skill::NATURE
+            | Skill::RELIGION => Ability::INTELLIGENCE,
+            Skill::ANIMALHANDLING
+            | Skill::INSIGHT
+            | Skill::MEDICINE
+            | Skill::PERCEPTION
+            | Skill::SURVIVAL => Ability::WISDOM,
+            Skill::DECEPTION | Skill::INTIMIDATION | Skill::PERFORMANCE | Skill::PERSUASION => {
+                Ability::CHARISMA
+            }
+        }
+    }
+
+    pub fn flag(&self) -> SkillFlags {
+        SkillFlags::from_bits_truncate(1 << (*self as u32))
+    }
+}
+
+/// Proficiency bonus for a character level, +2 at level 1 rising to +6 at level 17.
+pub fn proficiency_bonus(level: u8) -> i8 {
+    2 + (level.clamp(1, 20) as i8 - 1) / 4
+}
+
+/// Ability modifier plus the proficiency bonus, doubled for expertise. Saturates at `i8::MAX`.
+pub fn skill_bonus(
+    skill: Skill,
+    stats: &StatBlock,
+    proficiencies: &SkillProficiencies,
+    level: u8,
+) -> i8 {
+    let modifier = stats.modifier(skill.ability());
+    if proficiencies.expertise.contains(skill.flag()) {
+        modifier.saturating_add(2 * proficiency_bonus(level))
+    } else if proficiencies.proficient.contains(skill.flag()) {
+        modifier.saturating_add(proficiency_bonus(level))
+    } else {
+        modifier
+    }
+}
+
+pub fn passive_perception(stats: &StatBlock, proficiencies: &SkillProficiencies, level: u8) -> i8 {
+    skill_bonus(Skill::PERCEPTION, stats, proficiencies, level).saturating_add(10)
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_skills_tests {
+    use super::*;
+
+    #[test]
+    fn proficiency_bonus_follows_level() {
+        assert_eq!(proficiency_bonus(1), 2);
+        assert_eq!(proficiency_bonus(4), 2);
+        assert_eq!(proficiency_bonus(5), 3);
+        assert_eq!(proficiency_bonus(17), 6);
+        assert_eq!(proficiency_bonus(20), 6);
+    }
+
+    #[test]
+    fn skill_bonus_adds_proficiency_and_expertise() {
+        let stats = StatBlock::new(10, 16, 10, 12, 14, 8);
+        let proficiencies = SkillProficiencies {
+            proficient: SkillFlags::STEALTH | SkillFlags::PERCEPTION,
+            expertise: SkillFlags::STEALTH,
+        };
+        assert_eq!(skill_bonus(Skill::STEALTH, &stats, &proficiencies, 5), 9);
+        assert_eq!(skill_bonus(Skill::PERCEPTION, &stats, &proficiencies, 5), 5);
+        assert_eq!(
+            skill_bonus(Skill::PERSUASION, &stats, &proficiencies, 5),
+            -1
+        );
+        assert_eq!(passive_perception(&stats, &proficiencies, 5), 15);
+    }
+
+    #[test]
+    fn huge_scores_saturate() {
+        let stats = StatBlock::new(10, 255, 10, 10, 255, 10);
+        let proficiencies = SkillProficiencies {
+            proficient: SkillFlags::STEALTH | SkillFlags::PERCEPTION,
+            expertise: SkillFlags::STEALTH,
+        };
+        assert_eq!(skill_bonus(Skill::STEALTH, &stats, &proficiencies, 20), 127);
+        assert_eq!(
+            skill_bonus(Skill::PERCEPTION, &stats, &proficiencies, 20),
+            127
+        );
+        assert_eq!(passive_perception(&stats, &proficiencies, 20), 127);
+    }
+
+    #[test]
+    fn skill_flags_line_up_with_skills() {
+        assert_eq!(Skill::ACROBATICS.flag(), SkillFlags::ACROBATICS);
+        assert_eq!(Skill::SLEIGHTOFHAND.flag(), SkillFlags::SLEIGHTOFHAND);
+        assert_eq!(Skill::SURVIVAL.flag(), SkillFlags::SURVIVAL);
+    }
+}
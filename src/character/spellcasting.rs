@@ -0,0 +1,253 @@
+use std::fmt;
+
+use super::character_data::{
+    class_type::ClassType,
+    rest_kind::RestKind,
+    spell_slots::{PactSlots, SpellSlots},
+};
+
+/// Spell slots per spell level (1-9) for a full caster of character level 1-20.
+const FULL_CASTER_SLOTS: [[u8; 9]; 20] = [
+    [2, 0, 0, 0, 0, 0, 0, 0, 0],
+    [3, 0, 0, 0, 0, 0, 0, 0, 0],
+    [4, 2, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 2, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 1, 0, 0, 0, 0, 0],
+    [4, 3, 3, 2, 0, 0, 0, 0, 0],
+    [4, 3, 3, 3, 1, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 2, 1, 1],
+];
+
+#[derive(Debug, PartialEq)]
+pub enum SpellcastingError {
+    InvalidSlotLevel(u8),
+    NoSlotsRemaining(u8),
+}
+
+impl fmt::Display for SpellcastingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpellcastingError::InvalidSlotLevel(level) => {
+                write!(f, "{} is not a valid spell slot level", level)
+            }
+            SpellcastingError::NoSlotsRemaining(level) => {
+                write!(f, "No level {} spell slots remaining", level)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpellcastingError {}
+
+/// Spell slots and known/prepared spells for a single character.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spellcasting {
+    pub slots: SpellSlots,
+    pub spells: Vec<String>,
+}
+
+impl Spellcasting {
+    pub fn new(class: &ClassType, level: u8) -> Self {
+        Spellcasting {
+            slots: SpellSlots {
+                max: max_spell_slots(class, level),
+                used: [0; 9],
+                pact: pact_slots(class, level),
+            },
+            spells: Vec::new(),
+        }
+    }
+
+    /// Recalculates the maximum slots for a new class or level, keeping used slots where possible.
+    pub fn set_level(&mut self, class: &ClassType, level: u8) {
+        self.slots.max = max_spell_slots(class, level);
+        for (used, max) in self.slots.used.iter_mut().zip(self.slots.max) {
+            *used = (*used).min(max);
+        }
+        let pact = pact_slots(class, level);
+        self.slots.pact = PactSlots {
+            used: self.slots.pact.used.min(pact.max),
+            ..pact
+        };
+    }
+
+    /// Slots of `slot_level` left, counting ones written as used beyond the maximum as none left.
+    pub fn remaining(&self, slot_level: u8) -> u8 {
+        match slot_level {
+            1..=9 => {
+                let index = usize::from(slot_level - 1);
+                let mut remaining = self.slots.max[index].saturating_sub(self.slots.used[index]);
+                if self.slots.pact.level == slot_level {
+                    remaining = remaining
+                        .saturating_add(self.slots.pact.max.saturating_sub(self.slots.pact.used));
+                }
+                remaining
+            }
+            _ => 0,
+        }
+    }
+
+    /// Expends a slot of `slot_level`, falling back to a warlock pact slot of the same level.
+    pub fn cast(&mut self, slot_level: u8) -> Result<(), SpellcastingError> {
+        if !(1..=9).contains(&slot_level) {
+            return Err(SpellcastingError::InvalidSlotLevel(slot_level));
+        }
+        let index = usize::from(slot_level - 1);
+        if self.slots.used[index] < self.slots.max[index] {
+            self.slots.used[index] += 1;
+            Ok(())
+        } else if self.slots.pact.level == slot_level && self.slots.pact.used < self.slots.pact.max
+        {
+            self.slots.pact.used += 1;
+            Ok(())
+        } else {
+            Err(SpellcastingError::NoSlotsRemaining(slot_level))
+        }
+    }
+
+    /// A short rest restores pact slots; a long rest restores every slot.
+    pub fn rest(&mut self, rest: &RestKind) {
+        self.slots.pact.used = 0;
+        if *rest == RestKind::LONG {
+            self.slots.used = [0; 9];
+        }
+    }
+}
+
+pub fn max_spell_slots(class: &ClassType, level: u8) -> [u8; 9] {
+    let level = level.min(20);
+    let caster_level = match class {
+        ClassType::BARD
+        | ClassType::CLERIC
+        | ClassType::DRUID
+        | ClassType::SORCERER
+        | ClassType::WIZARD => level,
+        ClassType::ARTIFICER => level.div_ceil(2),
+        ClassType::PALADIN | ClassType::RANGER if level >= 2 => level.div_ceil(2),
+        _ => 0,
+    };
+    match caster_level {
+        0 => [0; 9],
+        _ => FULL_CASTER_SLOTS[usize::from(caster_level - 1)],
+    }
+}
+
+pub fn pact_slots(class: &ClassType, level: u8) -> PactSlots {
+    if *class != ClassType::WARLOCK || level == 0 {
+        return PactSlots::default();
+    }
+    let level = level.min(20);
+    PactSlots {
+        level: level.div_ceil(2).min(5),
+        max: match level {
+            1 => 1,
+            2..=10 => 2,
+            11..=16 => 3,
+            _ => 4,
+        },
+        used: 0,
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_spellcasting_tests {
+    use super::*;
+
+    #[test]
+    fn full_caster_slots_follow_table() {
+        assert_eq!(
+            max_spell_slots(&ClassType::WIZARD, 5),
+            [4, 3, 2, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            max_spell_slots(&ClassType::CLERIC, 20),
+            [4, 3, 3, 3, 3, 2, 2, 1, 1]
+        );
+        assert_eq!(max_spell_slots(&ClassType::BARD, 0), [0; 9]);
+    }
+
+    #[test]
+    fn half_caster_slots_follow_table() {
+        assert_eq!(max_spell_slots(&ClassType::PALADIN, 1), [0; 9]);
+        assert_eq!(
+            max_spell_slots(&ClassType::RANGER, 5),
+            [4, 2, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            max_spell_slots(&ClassType::ARTIFICER, 1),
+            [2, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(max_spell_slots(&ClassType::FIGHTER, 20), [0; 9]);
+    }
+
+    #[test]
+    fn warlock_gets_pact_slots_only() {
+        assert_eq!(max_spell_slots(&ClassType::WARLOCK, 11), [0; 9]);
+        assert_eq!(
+            pact_slots(&ClassType::WARLOCK, 11),
+            PactSlots {
+                level: 5,
+                max: 3,
+                used: 0
+            }
+        );
+        assert_eq!(pact_slots(&ClassType::WIZARD, 11), PactSlots::default());
+    }
+
+    #[test]
+    fn casting_uses_slots_until_empty() {
+        let mut spellcasting = Spellcasting::new(&ClassType::WIZARD, 1);
+        assert_eq!(spellcasting.cast(1), Ok(()));
+        assert_eq!(spellcasting.cast(1), Ok(()));
+        assert_eq!(
+            spellcasting.cast(1),
+            Err(SpellcastingError::NoSlotsRemaining(1))
+        );
+        assert_eq!(
+            spellcasting.cast(10),
+            Err(SpellcastingError::InvalidSlotLevel(10))
+        );
+    }
+
+    #[test]
+    fn rests_restore_slots() {
+        let mut spellcasting = Spellcasting::new(&ClassType::WARLOCK, 3);
+        spellcasting.cast(2).unwrap();
+        spellcasting.cast(2).unwrap();
+        assert_eq!(spellcasting.remaining(2), 0);
+        spellcasting.rest(&RestKind::SHORT);
+        assert_eq!(spellcasting.remaining(2), 2);
+
+        let mut spellcasting = Spellcasting::new(&ClassType::CLERIC, 3);
+        spellcasting.cast(2).unwrap();
+        spellcasting.rest(&RestKind::SHORT);
+        assert_eq!(spellcasting.remaining(2), 1);
+        spellcasting.rest(&RestKind::LONG);
+        assert_eq!(spellcasting.remaining(2), 2);
+    }
+
+    #[test]
+    fn overused_slots_leave_none() {
+        let mut spellcasting = Spellcasting::new(&ClassType::WARLOCK, 3);
+        spellcasting.slots.used[1] = 7;
+        spellcasting.slots.pact.used = 9;
+        assert_eq!(spellcasting.remaining(2), 0);
+        assert_eq!(
+            spellcasting.cast(2),
+            Err(SpellcastingError::NoSlotsRemaining(2))
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Conformance test vectors for JDCP implementations.
+//!
+//! A corpus file starts with a `version N` line, then holds one vector per line:
+//! `name hex => outcome`, where the outcome is the text form of the message the bytes decode to,
+//! or `error CATEGORY` for bytes that must be rejected. Blank lines and lines starting with `#`
+//! are skipped. Each vector is at most one frame, so anything left over after a message means
+//! its data size was wrong.
+//!
+//! An endpoint under test is sent each vector on a new TCP connection, which is then shut for
+//! writing. It replies with the outcome as one line and closes the connection.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    decode_jdcp,
+    message::{response_data_size, DecodeMode, InfoType, Message, MessageType, MAX_NAME_LENGTH},
+};
+
+/// The newest corpus version this crate can run.
+pub const VERSION: u32 = 1;
+
+const PREAMBLE: &[u8] = b"jdcp-";
+
+/// Why a frame was rejected, in the order the header is checked.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorCategory {
+    BADPREAMBLE,
+    UNKNOWNMESSAGETYPE,
+    BADNAME,
+    UNKNOWNINFOTYPE,
+    BADSIZE,
+    BADPAYLOAD,
+    /// A valid start of a frame that stops short.
+    INCOMPLETE,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 7] = [
+        ErrorCategory::BADPREAMBLE,
+        ErrorCategory::UNKNOWNMESSAGETYPE,
+        ErrorCategory::BADNAME,
+        ErrorCategory::UNKNOWNINFOTYPE,
+        ErrorCategory::BADSIZE,
+        ErrorCategory::BADPAYLOAD,
+        ErrorCategory::INCOMPLETE,
+    ];
+}
+
+/// What an implementation made of a frame.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    DECODED(Message<'static>),
+    FAILED(ErrorCategory),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::DECODED(message) => write!(f, "{}", message),
+            Outcome::FAILED(category) => write!(f, "error {:?}", category),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseCorpusError(String);
+
+impl fmt::Display for ParseCorpusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCorpusError {}
+
+impl FromStr for Outcome {
+    type Err = ParseCorpusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("error ") {
+            Some(category) => ErrorCategory::ALL
+                .into_iter()
+                .find(|known| format!("{:?}", known) == category)
+                .map(Outcome::FAILED)
+                .ok_or_else(|| ParseCorpusError(format!("unknown error category {:?}", category))),
+            None => s
+                .parse::<Message>()
+                .map(|message| Outcome::DECODED(message.into_owned()))
+                .map_err(|e| ParseCorpusError(e.to_string())),
+        }
+    }
+}
+
+/// Data size every message of this type and info type has, if it's fixed.
+fn fixed_size(message_type: MessageType, info_type: InfoType) -> Option<u16> {
+    match message_type {
+        MessageType::REQUEST | MessageType::SUBSCRIBE => Some(0),
+        MessageType::RESPONSE | MessageType::WRITE => response_data_size(info_type),
+    }
+}
+
+/// Finds the first part of a frame the decoder rejected in `mode`. Only right for bytes the
+/// decoder returned an error for rather than `Incomplete`, so that the header is all there up to
+/// the part it rejected, or for a name that has already run past [`MAX_NAME_LENGTH`].
+pub(crate) fn categorize(bytes: &[u8], mode: DecodeMode) -> ErrorCategory {
+    if !bytes.starts_with(PREAMBLE) {
+        return ErrorCategory::BADPREAMBLE;
+    }
+    let Some(Ok(message_type)) = bytes.get(PREAMBLE.len()).map(|b| MessageType::try_from(*b))
+    else {
+        return ErrorCategory::UNKNOWNMESSAGETYPE;
+    };
+    let name = bytes.get(PREAMBLE.len() + 1..).unwrap_or_default();
+    let letters = name.iter().take_while(|b| b.is_ascii_alphabetic()).count();
+    if letters == 0 || letters > MAX_NAME_LENGTH || name.get(letters) != Some(&0) {
+        return ErrorCategory::BADNAME;
+    }
+    let header = &name[letters + 1..];
+    let info_type = header.first().map(|b| match mode {
+        DecodeMode::STRICT => InfoType::try_from(*b),
+        DecodeMode::LENIENT => Ok(InfoType::try_from(*b).unwrap_or(InfoType::Unknown(*b))),
+    });
+    let Some(Ok(info_type)) = info_type else {
+        return ErrorCategory::UNKNOWNINFOTYPE;
+    };
+    let Some(&[low, high]) = header.get(1..3) else {
+        return ErrorCategory::BADSIZE;
+    };
+    let size = u16::from_le_bytes([low, high]);
+    match fixed_size(message_type, info_type) {
+        Some(fixed) if fixed != size => ErrorCategory::BADSIZE,
+        _ => ErrorCategory::BADPAYLOAD,
+    }
+}
+
+/// Decodes a frame with this crate's codec, the reference for every corpus vector.
+pub fn classify(bytes: &[u8]) -> Outcome {
+    match decode_jdcp(bytes) {
+        Ok(([], message)) => Outcome::DECODED(message.into_owned()),
+        Ok(_) => Outcome::FAILED(ErrorCategory::BADSIZE),
+        Err(nom::Err::Incomplete(_)) => Outcome::FAILED(ErrorCategory::INCOMPLETE),
+        Err(_) => Outcome::FAILED(categorize(bytes, DecodeMode::STRICT)),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Vector {
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub expected: Outcome,
+}
+
+impl fmt::Display for Vector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} => {}",
+            self.name,
+            hex::encode(&self.bytes),
+            self.expected
+        )
+    }
+}
+
+impl FromStr for Vector {
+    type Err = ParseCorpusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (frame, expected) = s.split_once(" => ").ok_or_else(|| {
+            ParseCorpusError(format!("expected `name hex => outcome`, got {:?}", s))
+        })?;
+        let Some((name, bytes)) = frame.split_once(' ') else {
+            return Err(ParseCorpusError(format!("missing hex in {:?}", frame)));
+        };
+        Ok(Vector {
+            name: name.to_string(),
+            bytes: hex::decode(bytes)
+                .map_err(|e| ParseCorpusError(format!("invalid hex: {}", e)))?,
+            expected: expected.parse()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Corpus {
+    pub version: u32,
+    pub vectors: Vec<Vector>,
+}
+
+/// Reads a corpus file, refusing versions newer than [`VERSION`].
+pub fn parse_corpus(corpus: &str) -> Result<Corpus, ParseCorpusError> {
+    let mut lines = corpus
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+    let version = lines
+        .next()
+        .and_then(|(_, line)| line.strip_prefix("version "))
+        .and_then(|version| version.parse::<u32>().ok())
+        .ok_or_else(|| ParseCorpusError("expected a `version N` line first".to_string()))?;
+    if version > VERSION {
+        return Err(ParseCorpusError(format!(
+            "corpus version {} is newer than {}, the newest this build runs",
+            version, VERSION
+        )));
+    }
+    let vectors = lines
+        .map(|(number, line)| {
+            line.parse()
+                .map_err(|e| ParseCorpusError(format!("line {}: {}", number + 1, e)))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Corpus { version, vectors })
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_conformance_tests {
+    use super::*;
+
+    #[test]
+    fn every_category_is_found() {
+        for (bytes, category) in [
+            (
+                &b"jdcx-\xAABart\x00\x06\x00\x00"[..],
+                ErrorCategory::BADPREAMBLE,
+            ),
+            (
+                b"jdcp-\x01Bart\x00\x06\x00\x00",
+                ErrorCategory::UNKNOWNMESSAGETYPE,
+            ),
+            (b"jdcp-\xAABa1t\x00\x06\x00\x00", ErrorCategory::BADNAME),
+            (b"jdcp-\xAA\x00\x06\x00\x00", ErrorCategory::BADNAME),
+            (
+                b"jdcp-\xAABart\x00\x42\x00\x00",
+                ErrorCategory::UNKNOWNINFOTYPE,
+            ),
+            (
+                b"jdcp-\xAABart\x00\x06\x02\x00\x22\x2A",
+                ErrorCategory::BADSIZE,
+            ),
+            (b"jdcp-\xBBBart\x00\x06\x01\x00\x22", ErrorCategory::BADSIZE),
+            (
+                b"jdcp-\xBBBart\x00\x06\x02\x00\x22\x2A\x00",
+                ErrorCategory::BADSIZE,
+            ),
+            (
+                b"jdcp-\xCCBart\x00\x0A\x01\x00\x03",
+                ErrorCategory::BADPAYLOAD,
+            ),
+            (
+                b"jdcp-\xBBBart\x00\x06\x02\x00\x22",
+                ErrorCategory::INCOMPLETE,
+            ),
+        ] {
+            assert_eq!(classify(bytes), Outcome::FAILED(category), "{:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn vectors_read_back() {
+        let line = "hp-response 6a6463702dbb42617274000602002229 => RESPONSE Bart HP 34/41";
+        let vector: Vector = line.parse().unwrap();
+        assert_eq!(vector.expected, classify(&vector.bytes));
+        assert_eq!(vector.to_string(), line);
+
+        let line = "short 6a6463 => error INCOMPLETE";
+        assert_eq!(line.parse::<Vector>().unwrap().to_string(), line);
+        assert!("bad 6a => error LATE".parse::<Vector>().is_err());
+    }
+
+    #[test]
+    fn newer_corpora_are_refused() {
+        assert!(parse_corpus("version 2\n").is_err());
+        assert!(parse_corpus("short 6a6463 => error INCOMPLETE\n").is_err());
+    }
+
+    #[test]
+    fn codec_passes_the_corpus() {
+        let corpus = std::fs::read_to_string("fixtures/conformance/v1.jdcpv").unwrap();
+        let corpus = parse_corpus(&corpus).unwrap();
+        assert_eq!(corpus.version, 1);
+        for vector in &corpus.vectors {
+            assert_eq!(classify(&vector.bytes), vector.expected, "{}", vector.name);
+        }
+    }
+}
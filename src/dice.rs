@@ -0,0 +1,627 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    str::FromStr,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{digit1, multispace0, one_of},
+    combinator::{all_consuming, map, map_res, opt, value},
+    error::{context, VerboseError},
+    multi::many0,
+    sequence::{delimited, pair, preceded, tuple},
+    IResult,
+};
+
+use crate::character::{
+    character_data::stat_block::{Ability, StatBlock},
+    skills::Skill,
+    Character,
+};
+
+pub const MAX_DICE: u32 = 100;
+pub const MAX_SIDES: u32 = 1000;
+/// Largest flat modifier a term may add or take away.
+pub const MAX_CONSTANT: u32 = 10_000;
+pub const MAX_TERMS: usize = 20;
+/// Longest expression text, in bytes.
+pub const MAX_EXPRESSION_LENGTH: usize = 100;
+
+/// Source of die rolls, so tests and servers can plug in their own randomness.
+pub trait DiceRng {
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniform roll from 1 to `sides`, which has to be at least 1.
+    fn roll_die(&mut self, sides: u32) -> u32 {
+        let sides = u64::from(sides);
+        let zone = u64::MAX - u64::MAX % sides;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % sides) as u32 + 1;
+            }
+        }
+    }
+}
+
+/// SplitMix64: small, fast and fully determined by its seed.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    pub fn from_entropy() -> Self {
+        SeededRng::new(RandomState::new().build_hasher().finish())
+    }
+}
+
+impl DiceRng for SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Keep {
+    ALL,
+    HIGHEST(u32),
+    LOWEST(u32),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Term {
+    DICE {
+        count: u32,
+        sides: u32,
+        keep: Keep,
+        negative: bool,
+    },
+    CONSTANT(i32),
+}
+
+/// A parsed expression such as `2d6+3`, `4d6kh3` or `1d20adv`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DiceExpression {
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DieResult {
+    pub sides: u32,
+    pub value: u32,
+    pub kept: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RollResult {
+    pub dice: Vec<DieResult>,
+    pub modifier: i32,
+    pub total: i32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DiceError {
+    Parse(String),
+    TooManyDice(u32),
+    TooManySides(u32),
+    /// A `0d` term.
+    NoDice,
+    /// A `d0` term.
+    NoSides,
+    /// Keeping none of the dice, or more dice than were rolled: the count to keep then the count.
+    BadKeep(u32, u32),
+    ConstantTooLarge(i32),
+    TooManyTerms(usize),
+    TooLong(usize),
+    /// The total doesn't fit in an `i32`, only possible for expressions built by hand.
+    Overflow,
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiceError::Parse(expression) => {
+                write!(f, "Unable to parse dice expression {:?}", expression)
+            }
+            DiceError::TooManyDice(count) => {
+                write!(f, "{} dice is more than the limit of {}", count, MAX_DICE)
+            }
+            DiceError::TooManySides(sides) => {
+                write!(f, "d{} is more than the limit of d{}", sides, MAX_SIDES)
+            }
+            DiceError::NoDice => write!(f, "Terms need at least one die"),
+            DiceError::NoSides => write!(f, "Dice need at least one side"),
+            DiceError::BadKeep(keep, count) => {
+                write!(f, "Can not keep {} of {} dice", keep, count)
+            }
+            DiceError::ConstantTooLarge(constant) => write!(
+                f,
+                "{} is more than the limit of {} either way",
+                constant, MAX_CONSTANT
+            ),
+            DiceError::TooManyTerms(terms) => {
+                write!(f, "{} terms is more than the limit of {}", terms, MAX_TERMS)
+            }
+            DiceError::TooLong(length) => write!(
+                f,
+                "{} characters is more than the limit of {}",
+                length, MAX_EXPRESSION_LENGTH
+            ),
+            DiceError::Overflow => write!(f, "The total is too large to add up"),
+        }
+    }
+}
+
+impl std::error::Error for DiceError {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RollMode {
+    NORMAL,
+    ADVANTAGE,
+    DISADVANTAGE,
+}
+
+type TextRes<'a, U> = IResult<&'a str, U, VerboseError<&'a str>>;
+
+fn number(i: &str) -> TextRes<'_, u32> {
+    map_res(digit1, str::parse)(i)
+}
+
+fn keep(i: &str) -> TextRes<'_, (Keep, u32)> {
+    alt((
+        map(preceded(tag("kh"), number), |n| (Keep::HIGHEST(n), 1)),
+        map(preceded(tag("kl"), number), |n| (Keep::LOWEST(n), 1)),
+        value((Keep::HIGHEST(1), 2), tag("adv")),
+        value((Keep::LOWEST(1), 2), tag("dis")),
+    ))(i)
+}
+
+fn dice(i: &str) -> TextRes<'_, Term> {
+    context(
+        "dice",
+        map(
+            tuple((opt(number), one_of("dD"), number, opt(keep))),
+            |(count, _, sides, keep)| {
+                let count = count.unwrap_or(1);
+                let (keep, multiplier) = match keep {
+                    Some((Keep::HIGHEST(1), 2)) => (Keep::HIGHEST(count), 2),
+                    Some((Keep::LOWEST(1), 2)) => (Keep::LOWEST(count), 2),
+                    Some(keep) => keep,
+                    None => (Keep::ALL, 1),
+                };
+                Term::DICE {
+                    count: count.saturating_mul(multiplier),
+                    sides,
+                    keep,
+                    negative: false,
+                }
+            },
+        ),
+    )(i)
+}
+
+fn term(i: &str) -> TextRes<'_, Term> {
+    alt((
+        dice,
+        map(number, |n| Term::CONSTANT(n.min(i32::MAX as u32) as i32)),
+    ))(i)
+}
+
+fn signed_term(i: &str) -> TextRes<'_, Term> {
+    map(
+        pair(delimited(multispace0, one_of("+-"), multispace0), term),
+        |(sign, term)| match (sign, term) {
+            (
+                '-',
+                Term::DICE {
+                    count, sides, keep, ..
+                },
+            ) => Term::DICE {
+                count,
+                sides,
+                keep,
+                negative: true,
+            },
+            ('-', Term::CONSTANT(n)) => Term::CONSTANT(-n),
+            (_, term) => term,
+        },
+    )(i)
+}
+
+fn expression(i: &str) -> TextRes<'_, DiceExpression> {
+    map(
+        all_consuming(delimited(
+            multispace0,
+            pair(term, many0(signed_term)),
+            multispace0,
+        )),
+        |(first, rest)| DiceExpression {
+            terms: [vec![first], rest].concat(),
+        },
+    )(i)
+}
+
+impl FromStr for DiceExpression {
+    type Err = DiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() > MAX_EXPRESSION_LENGTH {
+            return Err(DiceError::TooLong(s.len()));
+        }
+        let (_, expression) = expression(s).map_err(|_| DiceError::Parse(s.to_string()))?;
+        if expression.terms.len() > MAX_TERMS {
+            return Err(DiceError::TooManyTerms(expression.terms.len()));
+        }
+        let mut total_dice = 0u32;
+        for term in &expression.terms {
+            match term {
+                Term::DICE {
+                    count, sides, keep, ..
+                } => {
+                    total_dice = total_dice.saturating_add(*count);
+                    if *count == 0 {
+                        return Err(DiceError::NoDice);
+                    }
+                    if *sides == 0 {
+                        return Err(DiceError::NoSides);
+                    }
+                    if *sides > MAX_SIDES {
+                        return Err(DiceError::TooManySides(*sides));
+                    }
+                    if let Keep::HIGHEST(kept) | Keep::LOWEST(kept) = *keep {
+                        if kept == 0 || kept > *count {
+                            return Err(DiceError::BadKeep(kept, *count));
+                        }
+                    }
+                }
+                Term::CONSTANT(n) if n.unsigned_abs() > MAX_CONSTANT => {
+                    return Err(DiceError::ConstantTooLarge(*n))
+                }
+                Term::CONSTANT(_) => (),
+            }
+        }
+        if total_dice > MAX_DICE {
+            return Err(DiceError::TooManyDice(total_dice));
+        }
+        Ok(expression)
+    }
+}
+
+impl fmt::Display for DiceExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, term) in self.terms.iter().enumerate() {
+            match term {
+                Term::DICE {
+                    count,
+                    sides,
+                    keep,
+                    negative,
+                } => {
+                    if *negative {
+                        write!(f, "-")?;
+                    } else if index > 0 {
+                        write!(f, "+")?;
+                    }
+                    write!(f, "{}d{}", count, sides)?;
+                    match keep {
+                        Keep::ALL => (),
+                        Keep::HIGHEST(n) => write!(f, "kh{}", n)?,
+                        Keep::LOWEST(n) => write!(f, "kl{}", n)?,
+                    }
+                }
+                Term::CONSTANT(n) if index > 0 && *n >= 0 => write!(f, "+{}", n)?,
+                Term::CONSTANT(n) => write!(f, "{}", n)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DiceExpression {
+    pub fn roll(&self, rng: &mut impl DiceRng) -> Result<RollResult, DiceError> {
+        let mut dice = Vec::new();
+        let mut modifier = 0i32;
+        let mut total = 0i32;
+        for term in &self.terms {
+            match *term {
+                Term::DICE {
+                    count,
+                    sides,
+                    keep,
+                    negative,
+                } => {
+                    if sides == 0 {
+                        return Err(DiceError::NoSides);
+                    }
+                    let mut rolled: Vec<DieResult> = (0..count)
+                        .map(|_| DieResult {
+                            sides,
+                            value: rng.roll_die(sides),
+                            kept: true,
+                        })
+                        .collect();
+                    let mut order: Vec<usize> = (0..rolled.len()).collect();
+                    order.sort_by_key(|index| rolled[*index].value);
+                    let dropped = match keep {
+                        Keep::ALL => &order[..0],
+                        Keep::HIGHEST(n) => &order[..order.len().saturating_sub(n as usize)],
+                        Keep::LOWEST(n) => &order[(n as usize).min(order.len())..],
+                    };
+                    for index in dropped {
+                        rolled[*index].kept = false;
+                    }
+                    let sum = rolled
+                        .iter()
+                        .filter(|die| die.kept)
+                        .try_fold(0i32, |sum, die| {
+                            sum.checked_add(i32::try_from(die.value).ok()?)
+                        })
+                        .ok_or(DiceError::Overflow)?;
+                    total = if negative {
+                        total.checked_sub(sum)
+                    } else {
+                        total.checked_add(sum)
+                    }
+                    .ok_or(DiceError::Overflow)?;
+                    dice.extend(rolled);
+                }
+                Term::CONSTANT(n) => {
+                    modifier = modifier.checked_add(n).ok_or(DiceError::Overflow)?;
+                    total = total.checked_add(n).ok_or(DiceError::Overflow)?;
+                }
+            }
+        }
+        Ok(RollResult {
+            dice,
+            modifier,
+            total,
+        })
+    }
+}
+
+pub fn roll(expression: &str, rng: &mut impl DiceRng) -> Result<RollResult, DiceError> {
+    expression.parse::<DiceExpression>()?.roll(rng)
+}
+
+/// A d20 roll in the given mode plus a flat bonus.
+pub fn d20(mode: RollMode, bonus: i8) -> DiceExpression {
+    let (count, keep) = match mode {
+        RollMode::NORMAL => (1, Keep::ALL),
+        RollMode::ADVANTAGE => (2, Keep::HIGHEST(1)),
+        RollMode::DISADVANTAGE => (2, Keep::LOWEST(1)),
+    };
+    DiceExpression {
+        terms: vec![
+            Term::DICE {
+                count,
+                sides: 20,
+                keep,
+                negative: false,
+            },
+            Term::CONSTANT(bonus.into()),
+        ],
+    }
+}
+
+pub fn ability_check(
+    stats: &StatBlock,
+    ability: Ability,
+    mode: RollMode,
+    rng: &mut impl DiceRng,
+) -> Result<RollResult, DiceError> {
+    d20(mode, stats.modifier(ability)).roll(rng)
+}
+
+pub fn saving_throw(
+    character: &Character,
+    ability: Ability,
+    mode: RollMode,
+    rng: &mut impl DiceRng,
+) -> Result<RollResult, DiceError> {
+    d20(mode, character.saving_throw_bonus(ability)).roll(rng)
+}
+
+pub fn skill_check(
+    character: &Character,
+    skill: Skill,
+    mode: RollMode,
+    rng: &mut impl DiceRng,
+) -> Result<RollResult, DiceError> {
+    d20(mode, character.skill_bonus(skill)).roll(rng)
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_dice_tests {
+    use super::*;
+
+    /// Hands out the given rolls in order.
+    struct Loaded(Vec<u32>);
+
+    impl DiceRng for Loaded {
+        fn next_u64(&mut self) -> u64 {
+            unreachable!()
+        }
+
+        fn roll_die(&mut self, _sides: u32) -> u32 {
+            self.0.remove(0)
+        }
+    }
+
+    #[test]
+    fn parses_expressions() {
+        assert_eq!(
+            "2d6+3".parse(),
+            Ok(DiceExpression {
+                terms: vec![
+                    Term::DICE {
+                        count: 2,
+                        sides: 6,
+                        keep: Keep::ALL,
+                        negative: false
+                    },
+                    Term::CONSTANT(3)
+                ]
+            })
+        );
+        assert_eq!(
+            "1d20adv".parse::<DiceExpression>().unwrap().terms,
+            vec![Term::DICE {
+                count: 2,
+                sides: 20,
+                keep: Keep::HIGHEST(1),
+                negative: false
+            }]
+        );
+        assert_eq!(
+            "d8 - 1d4 - 2"
+                .parse::<DiceExpression>()
+                .unwrap()
+                .to_string(),
+            "1d8-1d4-2"
+        );
+        assert_eq!(
+            "4d6kh3".parse::<DiceExpression>().unwrap().to_string(),
+            "4d6kh3"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        assert_eq!(
+            "2d".parse::<DiceExpression>(),
+            Err(DiceError::Parse("2d".to_string()))
+        );
+        assert_eq!(
+            "2d6+".parse::<DiceExpression>(),
+            Err(DiceError::Parse("2d6+".to_string()))
+        );
+        assert_eq!(
+            "1000d6".parse::<DiceExpression>(),
+            Err(DiceError::TooManyDice(1000))
+        );
+        assert_eq!(
+            "1d10000".parse::<DiceExpression>(),
+            Err(DiceError::TooManySides(10000))
+        );
+        assert_eq!(
+            "2147483647+1".parse::<DiceExpression>(),
+            Err(DiceError::ConstantTooLarge(i32::MAX))
+        );
+        assert_eq!(
+            "1d6-10001".parse::<DiceExpression>(),
+            Err(DiceError::ConstantTooLarge(-10001))
+        );
+        assert_eq!(
+            "1+1+1+1+1+1+1+1+1+1+1+1+1+1+1+1+1+1+1+1+1".parse::<DiceExpression>(),
+            Err(DiceError::TooManyTerms(21))
+        );
+        let long = format!("1d6{}", " ".repeat(MAX_EXPRESSION_LENGTH));
+        assert_eq!(
+            long.parse::<DiceExpression>(),
+            Err(DiceError::TooLong(long.len()))
+        );
+        assert_eq!("1d0".parse::<DiceExpression>(), Err(DiceError::NoSides));
+        assert_eq!("0d6".parse::<DiceExpression>(), Err(DiceError::NoDice));
+        assert_eq!(
+            "2d6kh0".parse::<DiceExpression>(),
+            Err(DiceError::BadKeep(0, 2))
+        );
+        assert_eq!(
+            "2d6kl3".parse::<DiceExpression>(),
+            Err(DiceError::BadKeep(3, 2))
+        );
+        assert!("2d6kh2".parse::<DiceExpression>().is_ok());
+    }
+
+    #[test]
+    fn hand_built_expressions_that_overflow_are_errors() {
+        let expression = DiceExpression {
+            terms: vec![Term::CONSTANT(i32::MAX), Term::CONSTANT(1)],
+        };
+        assert_eq!(
+            expression.roll(&mut SeededRng::new(1)),
+            Err(DiceError::Overflow)
+        );
+        let expression = DiceExpression {
+            terms: vec![
+                Term::CONSTANT(i32::MIN),
+                Term::DICE {
+                    count: 1,
+                    sides: 6,
+                    keep: Keep::ALL,
+                    negative: true,
+                },
+            ],
+        };
+        assert_eq!(
+            expression.roll(&mut Loaded(vec![3])),
+            Err(DiceError::Overflow)
+        );
+        let expression = DiceExpression {
+            terms: vec![Term::DICE {
+                count: 1,
+                sides: 0,
+                keep: Keep::ALL,
+                negative: false,
+            }],
+        };
+        assert_eq!(
+            expression.roll(&mut SeededRng::new(1)),
+            Err(DiceError::NoSides)
+        );
+    }
+
+    #[test]
+    fn keeps_highest_and_lowest() {
+        let result = roll("4d6kh3", &mut Loaded(vec![3, 1, 6, 4])).unwrap();
+        assert_eq!(result.total, 13);
+        assert_eq!(
+            result
+                .dice
+                .iter()
+                .map(|die| die.kept)
+                .collect::<Vec<bool>>(),
+            vec![true, false, true, true]
+        );
+
+        let result = roll("1d20dis+2", &mut Loaded(vec![17, 4])).unwrap();
+        assert_eq!(result.total, 6);
+        assert_eq!(result.modifier, 2);
+    }
+
+    #[test]
+    fn seeded_rolls_are_deterministic() {
+        let first = roll("10d20+1", &mut SeededRng::new(42)).unwrap();
+        let second = roll("10d20+1", &mut SeededRng::new(42)).unwrap();
+        assert_eq!(first, second);
+        assert!(first.dice.iter().all(|die| (1..=20).contains(&die.value)));
+    }
+
+    #[test]
+    fn ability_checks_add_modifiers() {
+        let stats = StatBlock::new(10, 16, 10, 10, 10, 10);
+        let result = ability_check(
+            &stats,
+            Ability::DEXTERITY,
+            RollMode::ADVANTAGE,
+            &mut Loaded(vec![5, 12]),
+        )
+        .unwrap();
+        assert_eq!(result.total, 15);
+        assert_eq!(result.modifier, 3);
+    }
+}
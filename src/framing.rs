@@ -0,0 +1,195 @@
+use crate::{
+    conformance::{categorize, ErrorCategory},
+    decode_jdcp_with,
+    message::{DecodeMode, Message, MAX_NAME_LENGTH},
+};
+
+const PREAMBLE: &[u8] = b"jdcp-";
+
+/// The longest a valid frame can be: preamble, message type, name and its null, info type, data
+/// size and the largest payload.
+pub const MAX_FRAME_LENGTH: usize = PREAMBLE.len() + 1 + MAX_NAME_LENGTH + 4 + u16::MAX as usize;
+
+/// A frame cut from a byte stream: a decoded message, or bytes skipped to reach the next preamble.
+#[derive(Debug, PartialEq)]
+pub struct SplitFrame {
+    pub bytes: Vec<u8>,
+    pub message: Option<Message<'static>>,
+    /// Why the bytes were skipped, when they aren't a message: the part of a frame the decoder
+    /// rejected, or `BADPREAMBLE` for bytes that don't start one.
+    pub reason: Option<ErrorCategory>,
+}
+
+/// Cuts a byte stream into JDCP frames as it arrives, however it was split into reads. After
+/// bytes that aren't a frame it scans forward to the next `jdcp-` preamble and carries on from
+/// there, so one corrupt frame doesn't cost the connection. A frame still incomplete after
+/// [`MAX_FRAME_LENGTH`] bytes is skipped too, so a peer can't make it buffer without end.
+#[derive(Debug, Default)]
+pub struct FrameSplitter {
+    pending: Vec<u8>,
+    mode: DecodeMode,
+}
+
+/// Offset of the next preamble at or after `from`, counting one cut off at the end of `bytes`.
+fn find_preamble(bytes: &[u8], from: usize) -> usize {
+    (from..bytes.len())
+        .find(|at| {
+            let rest = &bytes[*at..];
+            rest.starts_with(PREAMBLE) || PREAMBLE.starts_with(rest)
+        })
+        .unwrap_or(bytes.len())
+}
+
+impl FrameSplitter {
+    /// A splitter decoding frames in `mode`; the default is strict.
+    pub fn new(mode: DecodeMode) -> Self {
+        FrameSplitter {
+            pending: Vec::new(),
+            mode,
+        }
+    }
+
+    /// Adds bytes from the stream and returns every frame they complete.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<SplitFrame> {
+        self.pending.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+        let mut at = 0;
+        while at < self.pending.len() {
+            match decode_jdcp_with(&self.pending[at..], self.mode) {
+                Ok((rest, message)) => {
+                    let end = self.pending.len() - rest.len();
+                    frames.push(SplitFrame {
+                        bytes: self.pending[at..end].to_vec(),
+                        message: Some(message.into_owned()),
+                        reason: None,
+                    });
+                    at = end;
+                }
+                Err(nom::Err::Incomplete(_)) if self.pending.len() - at <= MAX_FRAME_LENGTH => {
+                    break
+                }
+                Err(_) => {
+                    let next = find_preamble(&self.pending, at + 1);
+                    frames.push(SplitFrame {
+                        bytes: self.pending[at..next].to_vec(),
+                        message: None,
+                        reason: Some(categorize(&self.pending[at..], self.mode)),
+                    });
+                    at = next;
+                }
+            }
+        }
+        self.pending.drain(..at);
+        frames
+    }
+
+    /// Bytes of a frame that hasn't finished arriving.
+    pub fn pending(&self) -> &[u8] {
+        &self.pending
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_framing_tests {
+    use super::*;
+
+    #[test]
+    fn frames_split_across_reads() {
+        let mut splitter = FrameSplitter::default();
+        assert_eq!(splitter.push(b"jdcp-\xAABart\x00\x06"), vec![]);
+        let frames = splitter.push(b"\x00\x00jdcp-\xAALisa\x00\x01\x00\x00jdcp");
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].bytes, b"jdcp-\xAABart\x00\x06\x00\x00");
+        assert_eq!(
+            frames[1].message.as_ref().unwrap().to_string(),
+            "REQUEST Lisa STATS"
+        );
+        assert_eq!(splitter.pending(), b"jdcp");
+    }
+
+    #[test]
+    fn unterminated_names_are_skipped_at_the_frame_limit() {
+        let mut splitter = FrameSplitter::default();
+        let mut frames = splitter.push(b"jdcp-\xAA");
+        for _ in 0..1000 {
+            frames.extend(splitter.push(&[b'a'; 100]));
+            assert!(splitter.pending().len() <= MAX_FRAME_LENGTH);
+        }
+        assert_eq!(frames[0].reason, Some(ErrorCategory::BADNAME));
+        assert!(frames[0].bytes.len() > MAX_FRAME_LENGTH);
+        assert!(frames[1..]
+            .iter()
+            .all(|frame| frame.reason == Some(ErrorCategory::BADPREAMBLE)));
+        assert_eq!(
+            frames.iter().map(|frame| frame.bytes.len()).sum::<usize>(),
+            100_006
+        );
+
+        let frames = splitter.push(b"jdcp-\xAABart\x00\x06\x00\x00");
+        assert_eq!(
+            frames[0].message.as_ref().unwrap().to_string(),
+            "REQUEST Bart HP"
+        );
+    }
+
+    #[test]
+    fn skips_to_the_next_preamble() {
+        let mut splitter = FrameSplitter::default();
+        let frames = splitter.push(b"garbagejd");
+        assert_eq!(
+            frames,
+            vec![SplitFrame {
+                bytes: b"garbage".to_vec(),
+                message: None,
+                reason: Some(ErrorCategory::BADPREAMBLE),
+            }]
+        );
+        let frames = splitter.push(b"cp-\xAABart\x00\x06\x00\x00");
+        assert_eq!(
+            frames[0].message.as_ref().unwrap().to_string(),
+            "REQUEST Bart HP"
+        );
+        assert!(splitter.pending().is_empty());
+    }
+
+    #[test]
+    fn resyncs_after_a_corrupt_frame() {
+        let mut splitter = FrameSplitter::default();
+        let frames = splitter
+            .push(b"jdcp-\xBBBart\x00\x06\x03\x00\x22\x2A\x00jdcp-\xAABart\x00\x06\x00\x00");
+        let skipped: Vec<(usize, Option<ErrorCategory>)> = frames
+            .iter()
+            .map(|frame| (frame.bytes.len(), frame.reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![(17, Some(ErrorCategory::BADSIZE)), (14, None)]
+        );
+        assert_eq!(
+            frames[1].message.as_ref().unwrap().to_string(),
+            "REQUEST Bart HP"
+        );
+
+        let frames = splitter.push(b"jdcp-\x01jdcp-\xAABart\x00\x06\x00\x00");
+        assert_eq!(frames[0].bytes, b"jdcp-\x01");
+        assert_eq!(frames[0].reason, Some(ErrorCategory::UNKNOWNMESSAGETYPE));
+        assert!(frames[1].message.is_some());
+    }
+
+    #[test]
+    fn lenient_splitters_keep_newer_frames_whole() {
+        let newer = b"jdcp-\xBBBart\x00\x42\x03\x00jdc";
+        let frames = FrameSplitter::default().push(newer);
+        assert_eq!(frames[0].bytes, &newer[..newer.len() - 3]);
+        assert_eq!(frames[0].message, None);
+
+        let mut splitter = FrameSplitter::new(DecodeMode::LENIENT);
+        let frames = splitter.push(newer);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].bytes, newer);
+        assert_eq!(
+            frames[0].message.as_ref().unwrap().to_string(),
+            "RESPONSE Bart 66 6a6463"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt;
+
+use nom::{
+    error::{VerboseError, VerboseErrorKind},
+    Needed,
+};
+
+use crate::{
+    decode_jdcp,
+    message::{data_text, InfoType, Message, MessageType},
+};
+
+const PREAMBLE: &[u8] = b"jdcp-";
+const BYTES_PER_LINE: usize = 16;
+
+/// A run of bytes in a dissected message and what they mean.
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+    pub name: &'static str,
+    pub value: String,
+}
+
+/// Where decoding stopped, with the parser's error chain innermost first.
+#[derive(Debug, PartialEq)]
+pub struct DecodeFailure {
+    pub offset: usize,
+    pub chain: Vec<(usize, String)>,
+}
+
+/// One message's worth of input, split into fields. Offsets count from the start of the input.
+#[derive(Debug, PartialEq)]
+pub struct Dissection<'a> {
+    pub offset: usize,
+    pub bytes: &'a [u8],
+    pub fields: Vec<Field>,
+    pub message: Option<Message<'a>>,
+    pub failure: Option<DecodeFailure>,
+}
+
+/// Dissects every message in `input`, stopping at the first one that doesn't decode.
+pub fn dissect(input: &[u8]) -> Vec<Dissection<'_>> {
+    let mut dissections = Vec::new();
+    let mut offset = 0;
+    while offset < input.len() {
+        let dissection = dissect_message(&input[offset..], offset);
+        let failed = dissection.failure.is_some();
+        offset += dissection.bytes.len();
+        dissections.push(dissection);
+        if failed {
+            break;
+        }
+    }
+    dissections
+}
+
+fn take_field(
+    input: &[u8],
+    base: usize,
+    at: &mut usize,
+    len: usize,
+    name: &'static str,
+    describe: impl Fn(&[u8]) -> String,
+) -> Option<Field> {
+    let start = (*at).min(input.len());
+    let end = (start + len).min(input.len());
+    if start == end {
+        return None;
+    }
+    *at = end;
+    let bytes = &input[start..end];
+    Some(Field {
+        offset: base + start,
+        bytes: bytes.to_vec(),
+        name,
+        value: describe(bytes),
+    })
+}
+
+fn unknown_byte(bytes: &[u8]) -> String {
+    format!("unknown 0x{}", hex::encode(bytes))
+}
+
+fn header_fields(input: &[u8], base: usize) -> Vec<Field> {
+    let mut at = 0;
+    let name_len = input
+        .iter()
+        .skip(PREAMBLE.len() + 1)
+        .position(|byte| *byte == 0)
+        .map_or(input.len(), |nul| nul + 1);
+    let mut fields = vec![
+        take_field(input, base, &mut at, PREAMBLE.len(), "preamble", |bytes| {
+            if bytes == PREAMBLE {
+                String::from_utf8_lossy(bytes).to_string()
+            } else {
+                format!("{:?}, expected \"jdcp-\"", String::from_utf8_lossy(bytes))
+            }
+        }),
+        take_field(input, base, &mut at, 1, "message type", |bytes| {
+            MessageType::try_from(bytes[0])
+                .map_or_else(|_| unknown_byte(bytes), |known| format!("{:?}", known))
+        }),
+        take_field(input, base, &mut at, name_len, "name", |bytes| {
+            String::from_utf8_lossy(bytes.strip_suffix(b"\x00").unwrap_or(bytes)).to_string()
+        }),
+        take_field(input, base, &mut at, 1, "info type", |bytes| {
+            InfoType::try_from(bytes[0])
+                .map_or_else(|_| unknown_byte(bytes), |known| format!("{:?}", known))
+        }),
+    ];
+    let size = input.get(at..at + 2).map_or(0, |size| {
+        usize::from(u16::from_le_bytes([size[0], size[1]]))
+    });
+    fields.push(take_field(input, base, &mut at, 2, "data size", |bytes| {
+        if bytes.len() == 2 {
+            size.to_string()
+        } else {
+            "truncated".to_string()
+        }
+    }));
+    fields.push(take_field(input, base, &mut at, size, "payload", |bytes| {
+        if bytes.len() < size {
+            format!("truncated, {} of {} bytes", bytes.len(), size)
+        } else {
+            String::new()
+        }
+    }));
+    fields.into_iter().flatten().collect()
+}
+
+fn describe(kind: &VerboseErrorKind) -> String {
+    match kind {
+        VerboseErrorKind::Context(context) => format!("in {}", context),
+        VerboseErrorKind::Char(c) => format!("expected {:?}", c),
+        VerboseErrorKind::Nom(kind) => format!("{:?} failed", kind),
+    }
+}
+
+fn failure_from(input: &[u8], base: usize, error: VerboseError<&[u8]>) -> DecodeFailure {
+    let chain: Vec<(usize, String)> = error
+        .errors
+        .iter()
+        .map(|(rest, kind)| (base + input.len() - rest.len(), describe(kind)))
+        .collect();
+    DecodeFailure {
+        offset: chain.first().map_or(base, |(offset, _)| *offset),
+        chain,
+    }
+}
+
+fn dissect_message(input: &[u8], base: usize) -> Dissection<'_> {
+    let mut fields = header_fields(input, base);
+    match decode_jdcp(input) {
+        Ok((rest, message)) => {
+            if let Some(payload) = fields.iter_mut().find(|field| field.name == "payload") {
+                payload.value = message.data.as_ref().map(data_text).unwrap_or_default();
+            }
+            Dissection {
+                offset: base,
+                bytes: &input[..input.len() - rest.len()],
+                fields,
+                message: Some(message),
+                failure: None,
+            }
+        }
+        Err(error) => {
+            let failure = match error {
+                nom::Err::Error(e) | nom::Err::Failure(e) => failure_from(input, base, e),
+                nom::Err::Incomplete(needed) => DecodeFailure {
+                    offset: base + input.len(),
+                    chain: vec![(
+                        base + input.len(),
+                        match needed {
+                            Needed::Size(n) => format!("incomplete, {} more bytes needed", n),
+                            Needed::Unknown => "incomplete".to_string(),
+                        },
+                    )],
+                },
+            };
+            Dissection {
+                offset: base,
+                bytes: input,
+                fields,
+                message: None,
+                failure: Some(failure),
+            }
+        }
+    }
+}
+
+fn hex_column(bytes: &[u8]) -> String {
+    let shown: Vec<String> = bytes.iter().take(8).map(|b| hex::encode([*b])).collect();
+    if bytes.len() > 8 {
+        format!("{} ..", shown.join(" "))
+    } else {
+        shown.join(" ")
+    }
+}
+
+impl fmt::Display for Dissection<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "message at 0x{:04x} ({} bytes)",
+            self.offset,
+            self.bytes.len()
+        )?;
+        for field in &self.fields {
+            writeln!(
+                f,
+                "  {:04x}  {:<26} {:<13} {}",
+                field.offset,
+                hex_column(&field.bytes),
+                field.name,
+                field.value
+            )?;
+        }
+        if let Some(failure) = &self.failure {
+            writeln!(f, "decoding failed at 0x{:04x}", failure.offset)?;
+            for (line, chunk) in self.bytes.chunks(BYTES_PER_LINE).enumerate() {
+                let start = self.offset + line * BYTES_PER_LINE;
+                let hex: Vec<String> = chunk.iter().map(|b| hex::encode([*b])).collect();
+                writeln!(f, "  {:04x}  {}", start, hex.join(" "))?;
+                if (start..start + BYTES_PER_LINE).contains(&failure.offset) {
+                    writeln!(f, "        {}^^", " ".repeat((failure.offset - start) * 3))?;
+                }
+            }
+            if failure.offset == self.offset + self.bytes.len()
+                && self.bytes.len().is_multiple_of(BYTES_PER_LINE)
+            {
+                writeln!(f, "  {:04x}  ^^", failure.offset)?;
+            }
+            for (offset, reason) in &failure.chain {
+                writeln!(f, "    at 0x{:04x}: {}", offset, reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_inspect_tests {
+    use super::*;
+
+    #[test]
+    fn dissects_every_field() {
+        let dissections =
+            dissect(b"jdcp-\xBBBart\x00\x06\x02\x00\x22\x2Ajdcp-\xAALisa\x00\x01\x00\x00");
+        assert_eq!(dissections.len(), 2);
+        assert_eq!(
+            dissections[0]
+                .fields
+                .iter()
+                .map(|field| (field.offset, field.name, field.value.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "preamble", "jdcp-"),
+                (5, "message type", "RESPONSE"),
+                (6, "name", "Bart"),
+                (11, "info type", "HP"),
+                (12, "data size", "2"),
+                (14, "payload", "34/42"),
+            ]
+        );
+        assert_eq!(dissections[1].offset, 16);
+        assert_eq!(dissections[1].fields[2].value, "Lisa");
+        assert!(dissections.iter().all(|d| d.failure.is_none()));
+    }
+
+    #[test]
+    fn points_at_the_failure() {
+        let dissections = dissect(b"jdcp-\xBBBart\x00\x01\x06\xA0\x0C\x12\x12\x10\x0F\x0C");
+        let failure = dissections[0].failure.as_ref().unwrap();
+        assert_eq!(failure.offset, 12);
+        assert_eq!(
+            failure.chain,
+            vec![
+                (12, "Verify failed".to_string()),
+                (12, "in Data Size 6".to_string()),
+                (12, "in Stats Response".to_string()),
+                (11, "in Parse Response".to_string()),
+            ]
+        );
+        assert!(dissections[0]
+            .to_string()
+            .contains("decoding failed at 0x000c"));
+
+        let dissections = dissect(b"jdcp-\xEEBart\x00\x01\x00\x00");
+        let failure = dissections[0].failure.as_ref().unwrap();
+        assert_eq!(failure.offset, 5);
+        assert!(failure.chain.contains(&(0, "in message_type".to_string())));
+        assert_eq!(dissections[0].fields[1].value, "unknown 0xee");
+
+        let dissections = dissect(b"jdcp-\xBBBart\x00\x06\x02\x00\x22");
+        assert_eq!(dissections[0].fields[5].value, "truncated, 1 of 2 bytes");
+        assert_eq!(dissections[0].failure.as_ref().unwrap().offset, 15);
+    }
+}
@@ -1,6 +1,17 @@
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+pub mod capture;
 pub mod character;
+pub mod conformance;
+pub mod dice;
+pub mod framing;
+pub mod inspect;
 pub mod message;
-use message::{parse_character_name, parse_data, parse_message_type, Message};
+#[cfg(feature = "serde")]
+pub mod numeric;
+pub mod proxy;
+pub mod session;
+use message::{parse_character_name, parse_data_with, parse_message_type, DecodeMode, Message};
 use nom::{error::VerboseError, IResult};
 
 type Res<T, U> = IResult<T, U, VerboseError<T>>;
@@ -17,15 +28,25 @@ type Res<T, U> = IResult<T, U, VerboseError<T>>;
 //     }))
 // }
 
-pub fn decode_jdcp(input: &[u8]) -> Res<&[u8], Message> {
+/// Decodes one message from the front of `input`, returning the bytes after it.
+///
+/// Never panics, whatever the input: bytes that aren't a valid message are an `Error`, and a
+/// valid prefix that stops short is `Incomplete`. The targets under `fuzz/` check this.
+pub fn decode_jdcp(input: &[u8]) -> Res<&[u8], Message<'_>> {
+    decode_jdcp_with(input, DecodeMode::STRICT)
+}
+
+/// [`decode_jdcp`], keeping messages of info types this crate doesn't know in
+/// [`DecodeMode::LENIENT`].
+pub fn decode_jdcp_with(input: &[u8], mode: DecodeMode) -> Res<&[u8], Message<'_>> {
     let (input, message_type) = parse_message_type(input)?;
     let (input, character_name) = parse_character_name(input)?;
-    let (input, message_data) = parse_data(input, &message_type)?;
+    let (input, message_data) = parse_data_with(input, &message_type, mode)?;
     Ok((
         input,
         Message {
             message_type,
-            character_name,
+            character_name: character_name.into(),
             info_type: message_data.info_type,
             data_size: message_data.data_size,
             data: message_data.data,
@@ -43,7 +64,7 @@ mod josh_dnd_character_protocol_message_tests {
     use super::*;
     use nom::error::ErrorKind;
     use nom::error::VerboseErrorKind::{Context, Nom};
-    use nom::Err::Error;
+    use nom::Err::{Error, Failure};
 
     #[test]
     fn bytes_to_message_request_level_works() {
@@ -53,7 +74,7 @@ mod josh_dnd_character_protocol_message_tests {
                 &b""[..],
                 Message {
                     message_type: MessageType::REQUEST,
-                    character_name: "Bart",
+                    character_name: "Bart".into(),
                     info_type: InfoType::LEVEL,
                     data_size: 0,
                     data: None,
@@ -70,7 +91,7 @@ mod josh_dnd_character_protocol_message_tests {
                 &b""[..],
                 Message {
                     message_type: MessageType::REQUEST,
-                    character_name: "Bart",
+                    character_name: "Bart".into(),
                     info_type: InfoType::STATS,
                     data_size: 0,
                     data: None,
@@ -87,7 +108,7 @@ mod josh_dnd_character_protocol_message_tests {
                 &b""[..],
                 Message {
                     message_type: MessageType::RESPONSE,
-                    character_name: "Bart",
+                    character_name: "Bart".into(),
                     info_type: InfoType::STATS,
                     data_size: 6,
                     data: Some(CharacterData::STATS(StatBlock::new(12, 18, 18, 16, 15, 12))),
@@ -100,19 +121,19 @@ mod josh_dnd_character_protocol_message_tests {
     fn bytes_to_message_response_stats_error_works() {
         assert_eq!(
             decode_jdcp(&b"jdcp-\xBBBart\x00\x01\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..]),
-            Err(Error(VerboseError {
+            Err(Failure(VerboseError {
                 errors: vec![
                     (
-                        &b"\x01\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..],
+                        &b"\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..],
                         Nom(ErrorKind::Verify)
                     ),
                     (
-                        &b"\x01\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..],
-                        Context("HP Response")
+                        &b"\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..],
+                        Context("Data Size 6")
                     ),
                     (
-                        &b"\x01\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..],
-                        Nom(ErrorKind::Alt)
+                        &b"\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..],
+                        Context("Stats Response")
                     ),
                     (
                         &b"\x01\x06\xA0\x0C\x12\x12\x10\x0F\x0C"[..],
@@ -127,7 +148,7 @@ mod josh_dnd_character_protocol_message_tests {
     fn bytes_to_message_response_level_works() {
         let expected_message = Message {
             message_type: MessageType::RESPONSE,
-            character_name: "Bart",
+            character_name: "Bart".into(),
             info_type: InfoType::LEVEL,
             data_size: 1,
             data: Some(CharacterData::LEVEL(10)),
@@ -143,4 +164,75 @@ mod josh_dnd_character_protocol_message_tests {
         let result = decode_jdcp(&b"\xFF"[..]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn lenient_decoding_keeps_unknown_info_types() {
+        let newer = &b"jdcp-\xBBBart\x00\x42\x02\x00\x01\x02\xAA"[..];
+        assert!(matches!(decode_jdcp(newer), Err(Error(_))));
+        let (rest, message) = decode_jdcp_with(newer, DecodeMode::LENIENT).unwrap();
+        assert_eq!(rest, b"\xAA");
+        assert_eq!(message.info_type, InfoType::Unknown(0x42));
+        assert_eq!(
+            message.data,
+            Some(CharacterData::Unknown {
+                info_type: 0x42,
+                bytes: vec![1, 2],
+            })
+        );
+        assert_eq!(message.encode_jdcp(), &newer[..newer.len() - 1]);
+
+        let request = &b"jdcp-\xAABart\x00\xFF\x00\x00"[..];
+        assert!(decode_jdcp(request).is_err());
+        assert_eq!(
+            decode_jdcp_with(request, DecodeMode::LENIENT).map(|(_, m)| m.to_string()),
+            Ok("REQUEST Bart 255".to_string())
+        );
+        assert_eq!(
+            decode_jdcp_with(&newer[..12], DecodeMode::LENIENT),
+            Err(nom::Err::Incomplete(nom::Needed::new(2)))
+        );
+    }
+
+    #[test]
+    fn unknown_bytes_are_errors_not_panics() {
+        for bytes in [
+            &b"jdcp-\xFFBart\x00\x01\x00\x00"[..],
+            &b"jdcp-\xAABart\x00\xFF\x00\x00"[..],
+            &b"jdcp-\xCCBart\x00\x0A\x01\x00\x03"[..],
+            &b"jdcp-\xAAB\xC3\x00\x01\x00\x00"[..],
+        ] {
+            assert!(
+                matches!(decode_jdcp(bytes), Err(Error(_) | Failure(_))),
+                "{:?}",
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn damaged_messages_never_panic() {
+        let messages: [&[u8]; 5] = [
+            b"jdcp-\xBBBart\x00\x01\x06\x00\x0C\x12\x12\x10\x0F\x0C",
+            b"jdcp-\xBBBart\x00\x07\x15\x00\x04\x01\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x03\x02\x01",
+            b"jdcp-\xCCBart\x00\x08\x0A\x00Fire Bolt\x00",
+            b"jdcp-\xBBBart\x00\x13\x13\x001d20\x00\x11\x00\x00\x00\x05\x00\x00\x00\x01\x14\x00\x0C\x00\x01",
+            b"jdcp-\xCCBart\x00\x0A\x01\x00\x02",
+        ];
+        for message in messages {
+            assert!(decode_jdcp(message).is_ok(), "{:?}", message);
+            for end in 0..message.len() {
+                let _ = decode_jdcp(&message[..end]);
+            }
+            for at in 0..message.len() {
+                for byte in 0..=u8::MAX {
+                    let mut damaged = message.to_vec();
+                    damaged[at] = byte;
+                    if let Ok((_, decoded)) = decode_jdcp(&damaged) {
+                        let encoded = decoded.encode_jdcp();
+                        assert_eq!(decode_jdcp(&encoded), Ok((&b""[..], decoded)));
+                    }
+                }
+            }
+        }
+    }
 }
@@ -1,58 +1,52 @@
 mod character_name;
 mod data;
 mod message_type;
+mod text;
+
+use std::borrow::Cow;
 
-use byteorder::{LittleEndian, WriteBytesExt};
 pub use character_name::*;
 pub use data::*;
 pub use info_type::*;
 pub use message_type::*;
+pub(crate) use text::data_text;
+pub use text::ParseMessageError;
 
 use crate::character::character_data::CharacterData;
 
-use self::data::info_type::InfoType;
-
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Message<'a> {
     pub message_type: MessageType,
-    pub character_name: &'a str,
+    pub character_name: Cow<'a, str>,
     pub info_type: InfoType,
     pub data_size: u16,
     pub data: Option<CharacterData>,
 }
 
 impl Message<'_> {
-    pub fn encode_jdcp(self: &Self) -> Vec<u8> {
+    /// Copies the character name so the message no longer borrows the buffer it was decoded from.
+    pub fn into_owned(self) -> Message<'static> {
+        Message {
+            message_type: self.message_type,
+            character_name: Cow::Owned(self.character_name.into_owned()),
+            info_type: self.info_type,
+            data_size: self.data_size,
+            data: self.data,
+        }
+    }
+
+    pub fn encode_jdcp(&self) -> Vec<u8> {
         let message_type_bytes: &[u8] = &[self.message_type.discriminant()];
-        let char_bytes: &str = self.character_name;
+        let char_bytes: &str = &self.character_name;
         let null_byte: &[u8] = b"\x00";
         let info_type_bytes: &[u8] = &[self.info_type.discriminant()];
-        let mut data_size_bytes = Vec::new();
-        data_size_bytes
-            .write_u16::<LittleEndian>(self.data_size)
-            .expect("Could not write data_size");
-        let data_bytes: Vec<u8> = match &self.data {
-            Some(CharacterData::STATS(char_stats_block)) => [
-                char_stats_block.strength,
-                char_stats_block.dexterity,
-                char_stats_block.constitution,
-                char_stats_block.intelligence,
-                char_stats_block.wisdom,
-                char_stats_block.charisma,
-            ]
-            .to_vec(),
-            Some(CharacterData::AGE(char_age)) => {
-                let mut age = Vec::new();
-                age.write_u16::<LittleEndian>(*char_age)
-                    .expect("Age not written");
-                return age;
-            }
-            Some(CharacterData::CLASS(char_class)) => [char_class.discriminant()].to_vec(),
-            Some(CharacterData::RACE(char_race)) => [char_race.discriminant()].to_vec(),
-            Some(CharacterData::LEVEL(char_level)) => [*char_level].to_vec(),
-            Some(CharacterData::HP(char_hp)) => [char_hp.current, char_hp.max].to_vec(),
-            None => Vec::new(),
-        };
+        let data_size_bytes = self.data_size.to_le_bytes();
+        let data_bytes = self
+            .data
+            .as_ref()
+            .map(CharacterData::to_bytes)
+            .unwrap_or_default();
 
         [
             b"jdcp-",
@@ -71,7 +65,9 @@ impl Message<'_> {
 mod tests {
     use super::*;
     use crate::{
-        character::character_data::{health_points::HealthPoints, stat_block::StatBlock},
+        character::character_data::{
+            health_points::HealthPoints, spell_slots::SpellSlots, stat_block::StatBlock,
+        },
         decode_jdcp,
     };
     use nom::AsBytes;
@@ -82,7 +78,7 @@ mod tests {
             &b"jdcp-\xAABart\x00\x05\x00\x00"[..],
             Message {
                 message_type: MessageType::REQUEST,
-                character_name: "Bart",
+                character_name: "Bart".into(),
                 info_type: InfoType::LEVEL,
                 data_size: 0,
                 data: None,
@@ -97,7 +93,7 @@ mod tests {
             &b"jdcp-\xAABart\x00\x01\x00\x00"[..],
             Message {
                 message_type: MessageType::REQUEST,
-                character_name: "Bart",
+                character_name: "Bart".into(),
                 info_type: InfoType::STATS,
                 data_size: 0,
                 data: None,
@@ -112,7 +108,7 @@ mod tests {
             &b"jdcp-\xBBBart\x00\x01\x06\x00\x0C\x12\x12\x10\x0F\x0C"[..],
             Message {
                 message_type: MessageType::RESPONSE,
-                character_name: "Bart",
+                character_name: "Bart".into(),
                 info_type: InfoType::STATS,
                 data_size: 6,
                 data: Some(CharacterData::STATS(StatBlock::new(12, 18, 18, 16, 15, 12))),
@@ -125,7 +121,7 @@ mod tests {
     fn message_response_level_to_bytes_works() {
         let expected_message = Message {
             message_type: MessageType::RESPONSE,
-            character_name: "Bart",
+            character_name: "Bart".into(),
             info_type: InfoType::LEVEL,
             data_size: 1,
             data: Some(CharacterData::LEVEL(10)),
@@ -140,7 +136,7 @@ mod tests {
     fn back_and_forth_conversion() {
         let first_message = Message {
             message_type: MessageType::RESPONSE,
-            character_name: "Bart",
+            character_name: "Bart".into(),
             info_type: InfoType::HP,
             data_size: 2,
             data: Some(CharacterData::HP(HealthPoints {
@@ -150,8 +146,82 @@ mod tests {
         };
         let msg_vec = first_message.encode_jdcp();
         let buff = msg_vec.as_bytes();
-        let result_message = decode_jdcp(&buff).unwrap().1;
+        let result_message = decode_jdcp(buff).unwrap().1;
+
+        assert_eq!(first_message, result_message)
+    }
+
+    #[test]
+    fn spells_back_and_forth_conversion() {
+        let first_message = Message {
+            message_type: MessageType::RESPONSE,
+            character_name: "Bart".into(),
+            info_type: InfoType::SPELLS,
+            data_size: 21,
+            data: Some(CharacterData::SPELLS(vec![
+                "Shield".to_string(),
+                "Magic Missile".to_string(),
+            ])),
+        };
+        let msg_vec = first_message.encode_jdcp();
+        let result_message = decode_jdcp(&msg_vec).unwrap().1;
+
+        assert_eq!(first_message, result_message)
+    }
+
+    #[test]
+    fn write_cast_spell_to_bytes_works() {
+        assert_eq!(
+            &b"jdcp-\xCCBart\x00\x09\x01\x00\x02"[..],
+            Message {
+                message_type: MessageType::WRITE,
+                character_name: "Bart".into(),
+                info_type: InfoType::CASTSPELL,
+                data_size: 1,
+                data: Some(CharacterData::CASTSPELL(2)),
+            }
+            .encode_jdcp()
+        )
+    }
+
+    #[test]
+    fn spell_slots_back_and_forth_conversion() {
+        let slots = SpellSlots {
+            max: [4, 3, 3, 1, 0, 0, 0, 0, 0],
+            used: [2, 0, 1, 0, 0, 0, 0, 0, 0],
+            ..Default::default()
+        };
+        let first_message = Message {
+            message_type: MessageType::RESPONSE,
+            character_name: "Bart".into(),
+            info_type: InfoType::SPELLSLOTS,
+            data_size: SpellSlots::SIZE,
+            data: Some(CharacterData::SPELLSLOTS(slots)),
+        };
+        let msg_vec = first_message.encode_jdcp();
+        let result_message = decode_jdcp(&msg_vec).unwrap().1;
 
         assert_eq!(first_message, result_message)
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn message_serializes_with_names() {
+        let message = Message {
+            message_type: MessageType::RESPONSE,
+            character_name: "Bart".into(),
+            info_type: InfoType::HP,
+            data_size: 2,
+            data: Some(CharacterData::HP(HealthPoints {
+                current: 34,
+                max: 42,
+            })),
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            r#"{"message_type":"RESPONSE","character_name":"Bart","info_type":"HP","data_size":2,"data":{"HP":{"current":34,"max":42}}}"#
+        );
+        assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), message);
+    }
 }
@@ -1,17 +1,28 @@
 use nom::{
-    bytes::streaming::is_a, character::streaming::alpha1, error::context, sequence::terminated,
+    bytes::streaming::tag,
+    character::streaming::alpha1,
+    combinator::{map_res, verify},
+    error::context,
+    sequence::terminated,
 };
 use std::str;
 
 use crate::Res;
 
+/// Longest character name a frame may carry, in letters.
+pub const MAX_NAME_LENGTH: usize = 255;
+
 pub fn parse_character_name(i: &[u8]) -> Res<&[u8], &str> {
-    context("character_name", terminated(alpha1, is_a(&b"\x00"[..])))(i).map(|(i, result)| {
-        (
-            i,
-            str::from_utf8(result).expect("Error reading character name"),
-        )
-    })
+    context(
+        "character_name",
+        terminated(
+            map_res(
+                verify(alpha1, |name: &[u8]| name.len() <= MAX_NAME_LENGTH),
+                str::from_utf8,
+            ),
+            tag(&b"\x00"[..]),
+        ),
+    )(i)
 }
 
 #[cfg(test)]
@@ -23,4 +34,19 @@ mod josh_dnd_character_protocol_character_name_tests {
         let result = parse_character_name(&b"\x42\x61\x72\x74\x00\x01"[..]);
         assert_eq!(result, Ok((&b"\x01"[..], "Bart")));
     }
+
+    #[test]
+    fn only_one_null_ends_the_name() {
+        let result = parse_character_name(&b"Bart\x00\x00\x01"[..]);
+        assert_eq!(result, Ok((&b"\x00\x01"[..], "Bart")));
+    }
+
+    #[test]
+    fn names_have_a_limit() {
+        let name = "a".repeat(MAX_NAME_LENGTH);
+        let bytes = [name.as_bytes(), b"\x00"].concat();
+        assert_eq!(parse_character_name(&bytes), Ok((&b""[..], &name[..])));
+        let bytes = [name.as_bytes(), b"a\x00"].concat();
+        assert!(parse_character_name(&bytes).is_err());
+    }
 }
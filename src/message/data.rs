@@ -1,15 +1,21 @@
 pub mod character_data;
+pub mod custom;
 pub mod data_size;
 pub mod info_type;
+pub mod payload;
 
 use self::{
-    character_data::{parse_age, parse_class, parse_hp, parse_level, parse_race, parse_stats},
     data_size::parse_data_size,
-    info_type::{parse_info_type, InfoType},
+    info_type::{parse_any_info_type, parse_info_type, InfoType},
+    payload::{parse_payload, response_layout, CharacterData},
 };
 use super::MessageType;
-use crate::{character::character_data::CharacterData, Res};
-use nom::{branch::alt, combinator::verify, error::context, sequence::tuple};
+use crate::Res;
+use nom::{
+    combinator::{cut, verify},
+    error::context,
+    sequence::tuple,
+};
 
 #[derive(Debug, PartialEq)]
 pub struct MessageData {
@@ -22,156 +28,175 @@ pub struct MessageData {
 pub struct RequestData {
     pub info_type: InfoType,
 }
-pub fn parse_data<'a, 'b>(
+
+/// What the decoder does with info type bytes no info type uses.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DecodeMode {
+    /// Rejects them.
+    #[default]
+    STRICT,
+    /// Keeps them as [`InfoType::Unknown`], with the `data_size` bytes after them as
+    /// `CharacterData::Unknown`, so newer traffic can be passed on untouched.
+    LENIENT,
+}
+
+impl DecodeMode {
+    fn info_type(self, i: &[u8]) -> Res<&[u8], InfoType> {
+        match self {
+            DecodeMode::STRICT => parse_info_type(i),
+            DecodeMode::LENIENT => parse_any_info_type(i),
+        }
+    }
+}
+
+pub fn parse_data<'a>(i: &'a [u8], message_type: &MessageType) -> Res<&'a [u8], MessageData> {
+    parse_data_with(i, message_type, DecodeMode::STRICT)
+}
+
+pub fn parse_data_with<'a>(
     i: &'a [u8],
-    message_type: &'b MessageType,
+    message_type: &MessageType,
+    mode: DecodeMode,
 ) -> Res<&'a [u8], MessageData> {
-    if *message_type == MessageType::REQUEST {
-        parse_request(i)
+    if *message_type == MessageType::REQUEST || *message_type == MessageType::SUBSCRIBE {
+        request(i, mode)
     } else {
-        context(
-            "Parse Response",
-            alt((
-                parse_stats_response,
-                parse_age_response,
-                parse_class_response,
-                parse_race_response,
-                parse_level_response,
-                parse_hp_response,
-            )),
-        )(i)
+        context("Parse Response", |i| response(i, mode))(i)
     }
 }
-pub fn parse_stats_response(input: &[u8]) -> Res<&[u8], MessageData> {
+
+/// Reads the info type once and goes straight to its size check and payload parser. Anything
+/// wrong after the info type is a `Failure` naming the response, and the size it needed if fixed.
+pub fn parse_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    response(input, DecodeMode::STRICT)
+}
+
+fn response(input: &[u8], mode: DecodeMode) -> Res<&[u8], MessageData> {
+    let (input, info_type) = mode.info_type(input)?;
+    parse_response_data(input, info_type)
+}
+
+/// The data size every response of `info_type` has, when it's fixed.
+pub fn response_data_size(info_type: InfoType) -> Option<u16> {
+    response_layout(info_type).1.map(|(size, _)| size)
+}
+
+/// Everything in a response after its info type.
+fn parse_response_data(input: &[u8], info_type: InfoType) -> Res<&[u8], MessageData> {
+    let (name, fixed_size) = response_layout(info_type);
     context(
-        "Stats Response",
-        tuple((
-            verify(parse_info_type, |info: &InfoType| *info == InfoType::STATS),
-            verify(parse_data_size, |size: &u16| *size == 6),
-            parse_stats,
-        )),
+        name,
+        cut(move |i| {
+            let (i, data_size) = match fixed_size {
+                Some((size, expected)) => {
+                    context(expected, verify(parse_data_size, |s: &u16| *s == size))(i)?
+                }
+                None => parse_data_size(i)?,
+            };
+            parse_payload(i, info_type, data_size).map(|(i, data)| (i, (data_size, data)))
+        }),
     )(input)
-    .map(|(input, response)| {
+    .map(|(input, (data_size, data))| {
         (
             input,
             MessageData {
-                info_type: response.0,
-                data_size: response.1,
-                data: Some(response.2),
+                info_type,
+                data_size,
+                data: Some(data),
             },
         )
     })
 }
 
+/// A response whose info type has to be one of `info_types`.
+fn parse_response_of<'a>(input: &'a [u8], info_types: &[InfoType]) -> Res<&'a [u8], MessageData> {
+    let (input, info_type) =
+        verify(parse_info_type, |info: &InfoType| info_types.contains(info))(input)?;
+    parse_response_data(input, info_type)
+}
+
+pub fn parse_stats_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::STATS])
+}
+
 pub fn parse_age_response(input: &[u8]) -> Res<&[u8], MessageData> {
-    context(
-        "Age Response",
-        tuple((
-            verify(parse_info_type, |info: &InfoType| *info == InfoType::AGE),
-            verify(parse_data_size, |size: &u16| *size == 2),
-            parse_age,
-        )),
-    )(input)
-    .map(|(input, response)| {
-        (
-            input,
-            MessageData {
-                info_type: response.0,
-                data_size: response.1,
-                data: Some(response.2),
-            },
-        )
-    })
+    parse_response_of(input, &[InfoType::AGE])
 }
 
 pub fn parse_class_response(input: &[u8]) -> Res<&[u8], MessageData> {
-    context(
-        "Class Response",
-        tuple((
-            verify(parse_info_type, |info: &InfoType| *info == InfoType::CLASS),
-            verify(parse_data_size, |size: &u16| *size == 1),
-            parse_class,
-        )),
-    )(input)
-    .map(|(input, response)| {
-        (
-            input,
-            MessageData {
-                info_type: response.0,
-                data_size: response.1,
-                data: Some(response.2),
-            },
-        )
-    })
+    parse_response_of(input, &[InfoType::CLASS])
 }
 
 pub fn parse_race_response(input: &[u8]) -> Res<&[u8], MessageData> {
-    context(
-        "Race Response",
-        tuple((
-            verify(parse_info_type, |info: &InfoType| *info == InfoType::RACE),
-            verify(parse_data_size, |size: &u16| *size == 1),
-            parse_race,
-        )),
-    )(input)
-    .map(|(input, response)| {
-        (
-            input,
-            MessageData {
-                info_type: response.0,
-                data_size: response.1,
-                data: Some(response.2),
-            },
-        )
-    })
+    parse_response_of(input, &[InfoType::RACE])
 }
 
 pub fn parse_level_response(input: &[u8]) -> Res<&[u8], MessageData> {
-    context(
-        "Level Response",
-        tuple((
-            verify(parse_info_type, |info: &InfoType| *info == InfoType::LEVEL),
-            verify(parse_data_size, |size: &u16| *size == 1),
-            parse_level,
-        )),
-    )(input)
-    .map(|(input, response)| {
-        (
-            input,
-            MessageData {
-                info_type: response.0,
-                data_size: response.1,
-                data: Some(response.2),
-            },
-        )
-    })
+    parse_response_of(input, &[InfoType::LEVEL])
 }
-fn parse_hp_response(input: &[u8]) -> Res<&[u8], MessageData> {
-    context(
-        "HP Response",
-        tuple((
-            verify(parse_info_type, |info: &InfoType| *info == InfoType::HP),
-            verify(parse_data_size, |size: &u16| *size == 2),
-            parse_hp,
-        )),
-    )(input)
-    .map(|(input, response)| {
-        (
-            input,
-            MessageData {
-                info_type: response.0,
-                data_size: response.1,
-                data: Some(response.2),
-            },
-        )
-    })
+
+pub fn parse_hp_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::HP])
+}
+
+pub fn parse_spell_slots_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::SPELLSLOTS])
+}
+
+pub fn parse_spells_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::SPELLS])
+}
+
+pub fn parse_cast_spell_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::CASTSPELL])
+}
+
+pub fn parse_rest_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::REST])
+}
+
+/// Conditions, added conditions and removed conditions all share the same payload.
+pub fn parse_conditions_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(
+        input,
+        &[
+            InfoType::CONDITIONS,
+            InfoType::ADDCONDITIONS,
+            InfoType::REMOVECONDITIONS,
+        ],
+    )
+}
+
+pub fn parse_skills_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::SKILLS])
+}
+
+pub fn parse_combat_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::COMBAT])
+}
+
+/// XP and awarded XP share the same payload.
+pub fn parse_xp_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(input, &[InfoType::XP, InfoType::AWARDXP])
+}
+
+/// Roll requests, results and logs are all sized by `data_size`.
+pub fn parse_roll_response(input: &[u8]) -> Res<&[u8], MessageData> {
+    parse_response_of(
+        input,
+        &[InfoType::ROLL, InfoType::ROLLRESULT, InfoType::ROLLLOG],
+    )
 }
 
 pub fn parse_request(input: &[u8]) -> Res<&[u8], MessageData> {
+    request(input, DecodeMode::STRICT)
+}
+
+fn request(input: &[u8], mode: DecodeMode) -> Res<&[u8], MessageData> {
     context(
         "Request",
         tuple((
-            parse_info_type,
+            |i| mode.info_type(i),
             verify(parse_data_size, |size: &u16| *size == 0),
         )),
     )(input)
@@ -190,12 +215,21 @@ pub fn parse_request(input: &[u8]) -> Res<&[u8], MessageData> {
 #[cfg(test)]
 mod josh_dnd_character_protocol_data_tests {
     use crate::character::character_data::{
-        class_type::ClassType, health_points::HealthPoints, race_kind::RaceKind,
+        class_type::ClassType,
+        combat_stats::CombatStats,
+        conditions::{ConditionFlags, Conditions},
+        health_points::HealthPoints,
+        race_kind::RaceKind,
+        rest_kind::RestKind,
+        roll_record::RollRecord,
+        skill_proficiencies::{SkillFlags, SkillProficiencies},
+        spell_slots::{PactSlots, SpellSlots},
         stat_block::StatBlock,
     };
+    use crate::dice::{DieResult, RollResult};
     use nom::error::VerboseErrorKind::{Context, Nom};
     use nom::error::{ErrorKind, VerboseError};
-    use nom::Err::Error;
+    use nom::Err::{Error, Failure};
 
     use super::*;
 
@@ -300,6 +334,180 @@ mod josh_dnd_character_protocol_data_tests {
         );
     }
     #[test]
+    fn data_type_spell_slots_parser_works_independantly() {
+        let incoming_bytes = &b"\x07\x15\x00\x04\x01\x03\x00\x02\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::SPELLSLOTS,
+            data_size: 21u16,
+            data: Some(CharacterData::SPELLSLOTS(SpellSlots {
+                max: [4, 3, 2, 0, 0, 0, 0, 0, 0],
+                used: [1, 0, 2, 0, 0, 0, 0, 0, 0],
+                pact: PactSlots::default(),
+            })),
+        };
+
+        assert_eq!(
+            parse_spell_slots_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_spells_parser_works_independantly() {
+        let incoming_bytes = &b"\x08\x15\x00Shield\x00Magic Missile\x00\xAA"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::SPELLS,
+            data_size: 21u16,
+            data: Some(CharacterData::SPELLS(vec![
+                "Shield".to_string(),
+                "Magic Missile".to_string(),
+            ])),
+        };
+
+        assert_eq!(
+            parse_spells_response(incoming_bytes),
+            Ok((&b"\xAA"[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_spells_parser_rejects_unterminated_name() {
+        let result = parse_spells_response(&b"\x08\x06\x00Shield"[..]);
+        assert!(result.is_err());
+    }
+    #[test]
+    fn data_type_cast_spell_parser_works_independantly() {
+        let incoming_bytes = &b"\x09\x01\x00\x03"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::CASTSPELL,
+            data_size: 1u16,
+            data: Some(CharacterData::CASTSPELL(3)),
+        };
+
+        assert_eq!(
+            parse_cast_spell_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_rest_parser_works_independantly() {
+        let incoming_bytes = &b"\x0A\x01\x00\x02"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::REST,
+            data_size: 1u16,
+            data: Some(CharacterData::REST(RestKind::LONG)),
+        };
+
+        assert_eq!(
+            parse_rest_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_conditions_parser_works_independantly() {
+        let incoming_bytes = &b"\x0C\x03\x00\x00\x02\x01"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::ADDCONDITIONS,
+            data_size: 3u16,
+            data: Some(CharacterData::ADDCONDITIONS(Conditions::new(
+                ConditionFlags::POISONED,
+                1,
+            ))),
+        };
+
+        assert_eq!(
+            parse_conditions_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_skills_parser_works_independantly() {
+        let incoming_bytes = &b"\x0E\x06\x00\x00\x08\x01\x00\x00\x01"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::SKILLS,
+            data_size: 6u16,
+            data: Some(CharacterData::SKILLS(SkillProficiencies {
+                proficient: SkillFlags::PERCEPTION | SkillFlags::STEALTH,
+                expertise: SkillFlags::STEALTH,
+            })),
+        };
+
+        assert_eq!(
+            parse_skills_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_combat_parser_works_independantly() {
+        let incoming_bytes = &b"\x0F\x03\x00\x10\xFF\x19"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::COMBAT,
+            data_size: 3u16,
+            data: Some(CharacterData::COMBAT(CombatStats {
+                armor_class: 16,
+                initiative: -1,
+                speed: 25,
+            })),
+        };
+
+        assert_eq!(
+            parse_combat_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_xp_parser_works_independantly() {
+        let incoming_bytes = &b"\x11\x04\x00\x64\x19\x00\x00"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::AWARDXP,
+            data_size: 4u16,
+            data: Some(CharacterData::AWARDXP(6500)),
+        };
+
+        assert_eq!(
+            parse_xp_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_roll_parser_works_independantly() {
+        let incoming_bytes = &b"\x12\x05\x00\x32d6+3\xAA"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::ROLL,
+            data_size: 5u16,
+            data: Some(CharacterData::ROLL("2d6+3".to_string())),
+        };
+
+        assert_eq!(
+            parse_roll_response(incoming_bytes),
+            Ok((&b"\xAA"[..], expected_result))
+        );
+    }
+    #[test]
+    fn data_type_roll_result_parser_works_independantly() {
+        let incoming_bytes =
+            &b"\x13\x14\x00\x31d6+1\x00\x05\x00\x00\x00\x01\x00\x00\x00\x01\x06\x00\x04\x00\x01"[..];
+        let expected_result = MessageData {
+            info_type: InfoType::ROLLRESULT,
+            data_size: 20u16,
+            data: Some(CharacterData::ROLLRESULT(RollRecord {
+                expression: "1d6+1".to_string(),
+                result: RollResult {
+                    dice: vec![DieResult {
+                        sides: 6,
+                        value: 4,
+                        kept: true,
+                    }],
+                    modifier: 1,
+                    total: 5,
+                },
+            })),
+        };
+
+        assert_eq!(
+            parse_roll_response(incoming_bytes),
+            Ok((&b""[..], expected_result))
+        );
+    }
+    #[test]
     fn data_request_works() {
         let expected_result = MessageData {
             info_type: InfoType::AGE,
@@ -322,4 +530,26 @@ mod josh_dnd_character_protocol_data_tests {
             }))
         )
     }
+
+    #[test]
+    fn response_errors_name_the_info_type_and_size() {
+        let result = parse_data(&b"\x06\x03\x00\x22\x2A\x00"[..], &MessageType::RESPONSE);
+        assert_eq!(
+            result,
+            Err(Failure(VerboseError {
+                errors: vec![
+                    (&b"\x03\x00\x22\x2A\x00"[..], Nom(ErrorKind::Verify)),
+                    (&b"\x03\x00\x22\x2A\x00"[..], Context("Data Size 2")),
+                    (&b"\x03\x00\x22\x2A\x00"[..], Context("HP Response")),
+                    (&b"\x06\x03\x00\x22\x2A\x00"[..], Context("Parse Response")),
+                ]
+            }))
+        );
+        let result = parse_data(&b"\x0A\x01\x00\x03"[..], &MessageType::WRITE);
+        assert!(
+            matches!(&result, Err(Failure(e)) if e.errors.contains(&(&b"\x03"[..], Context("Info Type Rest")))),
+            "{:?}",
+            result
+        );
+    }
 }
@@ -1,32 +1,169 @@
 use nom::{
-    bytes::streaming::take,
+    bytes::{
+        complete::{self, tag, take_till1},
+        streaming::take,
+    },
+    combinator::{all_consuming, map, map_parser, map_res, rest, verify},
     error::context,
-    number::streaming::{le_u16, u8},
+    multi::{length_count, many0},
+    number::{
+        complete::{le_i32, u8 as complete_u8},
+        streaming::{le_u16, le_u32, u8},
+    },
+    sequence::{terminated, tuple},
 };
+use std::str;
 
-use crate::{Res, character::character_data::CharacterData};
+use crate::{
+    character::character_data::{
+        class_type::ClassType,
+        combat_stats::CombatStats,
+        conditions::Conditions,
+        health_points::HealthPoints,
+        race_kind::RaceKind,
+        rest_kind::RestKind,
+        roll_record::{RollLogEntry, RollRecord},
+        skill_proficiencies::SkillProficiencies,
+        spell_slots::SpellSlots,
+        stat_block::StatBlock,
+    },
+    dice::{DieResult, RollResult},
+    Res,
+};
+
+pub fn parse_stats(i: &[u8]) -> Res<&[u8], StatBlock> {
+    context("Info Type Stats", take(6u8))(i).map(|(i, result)| (i, result.into()))
+}
+
+pub fn parse_age(i: &[u8]) -> Res<&[u8], u16> {
+    context("Info Type Age", le_u16)(i)
+}
+
+pub fn parse_class(i: &[u8]) -> Res<&[u8], ClassType> {
+    context("Info Type Class", take(1u8))(i).map(|(i, result)| (i, result.into()))
+}
+pub fn parse_race(i: &[u8]) -> Res<&[u8], RaceKind> {
+    context("Info Type Race", take(1u8))(i).map(|(i, result)| (i, result.into()))
+}
+pub fn parse_level(i: &[u8]) -> Res<&[u8], u8> {
+    context("Info Type Level", u8)(i)
+}
+
+pub fn parse_hp(i: &[u8]) -> Res<&[u8], HealthPoints> {
+    context("Info Type HP", take(2u8))(i).map(|(i, result)| (i, result.into()))
+}
 
-pub fn parse_stats(i: &[u8]) -> Res<&[u8], CharacterData> {
-    context("Info Type Stats", take(6u8))(i)
-        .map(|(i, result)| (i, CharacterData::STATS(result.into())))
+pub fn parse_spell_slots(i: &[u8]) -> Res<&[u8], SpellSlots> {
+    context("Info Type Spell Slots", take(SpellSlots::SIZE))(i)
+        .map(|(i, result)| (i, result.into()))
 }
 
-pub fn parse_age(i: &[u8]) -> Res<&[u8], CharacterData> {
-    context("Info Type Age", le_u16)(i).map(|(i, result)| (i, CharacterData::AGE(result.into())))
+/// Spell names are null terminated and packed back to back, so `size` bytes are needed up front.
+pub fn parse_spells(i: &[u8], size: u16) -> Res<&[u8], Vec<String>> {
+    context(
+        "Info Type Spells",
+        map_parser(
+            take(size),
+            all_consuming(many0(terminated(
+                map_res(take_till1(|b| b == 0), str::from_utf8),
+                tag(&b"\x00"[..]),
+            ))),
+        ),
+    )(i)
+    .map(|(i, result)| (i, result.into_iter().map(String::from).collect()))
 }
 
-pub fn parse_class(i: &[u8]) -> Res<&[u8], CharacterData> {
-    context("Info Type Class", take(1u8))(i)
-        .map(|(i, result)| (i, CharacterData::CLASS(result.into())))
+pub fn parse_cast_spell(i: &[u8]) -> Res<&[u8], u8> {
+    context("Info Type Cast Spell", u8)(i)
 }
-pub fn parse_race(i: &[u8]) -> Res<&[u8], CharacterData> {
-    context("Info Type Race", take(1u8))(i)
-        .map(|(i, result)| (i, CharacterData::RACE(result.into())))
+
+pub fn parse_rest(i: &[u8]) -> Res<&[u8], RestKind> {
+    context("Info Type Rest", map_res(u8, RestKind::try_from))(i)
+}
+
+pub fn parse_conditions(i: &[u8]) -> Res<&[u8], Conditions> {
+    context("Info Type Conditions", take(Conditions::SIZE))(i).map(|(i, result)| (i, result.into()))
 }
-pub fn parse_level(i: &[u8]) -> Res<&[u8], CharacterData> {
-    context("Info Type Level", u8)(i).map(|(i, result)| (i, CharacterData::LEVEL(result.into())))
+
+pub fn parse_skills(i: &[u8]) -> Res<&[u8], SkillProficiencies> {
+    context("Info Type Skills", take(SkillProficiencies::SIZE))(i)
+        .map(|(i, result)| (i, result.into()))
+}
+
+pub fn parse_combat(i: &[u8]) -> Res<&[u8], CombatStats> {
+    context("Info Type Combat", take(CombatStats::SIZE))(i).map(|(i, result)| (i, result.into()))
+}
+
+pub fn parse_xp(i: &[u8]) -> Res<&[u8], u32> {
+    context("Info Type XP", le_u32)(i)
+}
+
+/// The dice expression to roll, e.g. `2d6+3`, taking up the whole payload. Empty expressions
+/// are refused.
+pub fn parse_roll(i: &[u8], size: u16) -> Res<&[u8], String> {
+    context(
+        "Info Type Roll",
+        map_parser(
+            take(size),
+            all_consuming(verify(map_res(rest, str::from_utf8), |expression: &str| {
+                !expression.is_empty()
+            })),
+        ),
+    )(i)
+    .map(|(i, result)| (i, result.to_string()))
+}
+
+fn parse_text(i: &[u8]) -> Res<&[u8], String> {
+    map(
+        terminated(
+            map_res(take_till1(|b| b == 0), str::from_utf8),
+            tag(&b"\x00"[..]),
+        ),
+        String::from,
+    )(i)
+}
+
+/// A rolled expression can't hold ` => `, which ends it in the text form.
+fn parse_roll_record(i: &[u8]) -> Res<&[u8], RollRecord> {
+    map(
+        tuple((
+            verify(parse_text, |expression: &String| {
+                !expression.contains(" => ")
+            }),
+            le_i32,
+            le_i32,
+            length_count(
+                complete_u8,
+                map(complete::take(RollRecord::DIE_SIZE), DieResult::from),
+            ),
+        )),
+        |(expression, total, modifier, dice)| RollRecord {
+            expression,
+            result: RollResult {
+                dice,
+                modifier,
+                total,
+            },
+        },
+    )(i)
+}
+
+pub fn parse_roll_result(i: &[u8], size: u16) -> Res<&[u8], RollRecord> {
+    context(
+        "Info Type Roll Result",
+        map_parser(take(size), all_consuming(parse_roll_record)),
+    )(i)
 }
 
-pub fn parse_hp(i: &[u8]) -> Res<&[u8], CharacterData> {
-    context("Info Type HP", take(2u8))(i).map(|(i, result)| (i, CharacterData::HP(result.into())))
+pub fn parse_roll_log(i: &[u8], size: u16) -> Res<&[u8], Vec<RollLogEntry>> {
+    context(
+        "Info Type Roll Log",
+        map_parser(
+            take(size),
+            all_consuming(many0(map(
+                tuple((parse_text, parse_roll_record)),
+                |(roller, record)| RollLogEntry { roller, record },
+            ))),
+        ),
+    )(i)
 }
@@ -0,0 +1,381 @@
+//! Info types 0x80 to 0xFE are left for vendors to carry their own data, e.g. a homebrew sanity
+//! score. Their payloads decode as [`CustomData`] sized by `data_size`, holding the raw bytes and,
+//! once a [`JdcpPayload`] is registered for the info type, the value it decodes them to:
+//!
+//! ```ignore
+//! struct Sanity;
+//!
+//! impl JdcpPayload for Sanity {
+//!     type Data = u8;
+//!
+//!     const INFO_TYPE: InfoType = InfoType::Custom(0x80);
+//!     const SIZE: Option<u16> = Some(1);
+//!
+//!     fn decode(i: &[u8], _: u16) -> Res<&[u8], u8> {
+//!         nom::number::complete::u8(i)
+//!     }
+//!
+//!     fn encode(sanity: &u8) -> Vec<u8> {
+//!         vec![*sanity]
+//!     }
+//! }
+//!
+//! register_payload::<Sanity>()?;
+//! ```
+
+use std::{any::Any, collections::BTreeMap, fmt, ops::RangeInclusive, sync::RwLock};
+
+use nom::{
+    bytes::streaming::take,
+    combinator::{all_consuming, complete},
+    error::context,
+};
+
+use super::payload::{InfoType, JdcpPayload};
+use crate::Res;
+
+/// Info type bytes decoded as [`InfoType::Custom`].
+pub const CUSTOM_INFO_TYPES: RangeInclusive<u8> = 0x80..=0xFE;
+
+type Value = Box<dyn Any + Send + Sync>;
+
+/// Reads the whole of a registered payload.
+type Decoder = fn(&[u8], u16) -> Res<&[u8], Value>;
+
+static REGISTERED: RwLock<BTreeMap<u8, Decoder>> = RwLock::new(BTreeMap::new());
+
+#[derive(Debug, PartialEq)]
+pub enum CustomPayloadError {
+    NotCustom(InfoType),
+    AlreadyRegistered(u8),
+}
+
+impl fmt::Display for CustomPayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomPayloadError::NotCustom(info_type) => write!(
+                f,
+                "{:?} is not in the custom info type range 0x80 to 0xFE",
+                info_type
+            ),
+            CustomPayloadError::AlreadyRegistered(info_type) => {
+                write!(f, "Info type {:#04X} already has a payload", info_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CustomPayloadError {}
+
+fn decode_registered<P>(i: &[u8], size: u16) -> Res<&[u8], Value>
+where
+    P: JdcpPayload,
+    P::Data: Send + Sync + 'static,
+{
+    all_consuming(|i| P::decode(i, size))(i).map(|(i, data)| (i, Box::new(data) as Value))
+}
+
+/// Decodes payloads of `P::INFO_TYPE` with `P` from now on, for every decoder in the process.
+pub fn register_payload<P>() -> Result<(), CustomPayloadError>
+where
+    P: JdcpPayload,
+    P::Data: Send + Sync + 'static,
+{
+    let InfoType::Custom(info_type) = P::INFO_TYPE else {
+        return Err(CustomPayloadError::NotCustom(P::INFO_TYPE));
+    };
+    let mut registered = REGISTERED.write().unwrap_or_else(|e| e.into_inner());
+    if registered.contains_key(&info_type) {
+        return Err(CustomPayloadError::AlreadyRegistered(info_type));
+    }
+    registered.insert(info_type, decode_registered::<P>);
+    Ok(())
+}
+
+/// Stops decoding payloads of `P::INFO_TYPE` with `P`, so another payload can be registered for
+/// it. Returns whether a payload was registered.
+pub fn unregister_payload<P>() -> bool
+where
+    P: JdcpPayload,
+{
+    let InfoType::Custom(info_type) = P::INFO_TYPE else {
+        return false;
+    };
+    REGISTERED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&info_type)
+        .is_some()
+}
+
+/// The payload of a custom info type. Two are equal when their info type and bytes are.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomData {
+    pub info_type: u8,
+    pub bytes: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    value: Option<Value>,
+}
+
+impl CustomData {
+    /// Bytes nothing has been registered to decode.
+    pub fn raw(info_type: u8, bytes: Vec<u8>) -> Self {
+        CustomData {
+            info_type,
+            bytes,
+            value: None,
+        }
+    }
+
+    /// Encodes `data` with `P`, or `None` when `P` isn't a custom info type.
+    pub fn new<P>(data: P::Data) -> Option<Self>
+    where
+        P: JdcpPayload,
+        P::Data: Send + Sync + 'static,
+    {
+        let InfoType::Custom(info_type) = P::INFO_TYPE else {
+            return None;
+        };
+        let bytes = P::encode(&data);
+        if let Some(size) = P::SIZE {
+            debug_assert_eq!(bytes.len(), usize::from(size), "{:?} encoded", P::INFO_TYPE);
+        }
+        Some(CustomData {
+            info_type,
+            bytes,
+            value: Some(Box::new(data)),
+        })
+    }
+
+    /// The decoded value, when `P` is the payload registered for this info type.
+    pub fn get<P>(&self) -> Option<&P::Data>
+    where
+        P: JdcpPayload,
+        P::Data: 'static,
+    {
+        match P::INFO_TYPE {
+            InfoType::Custom(info_type) if info_type == self.info_type => {
+                self.value.as_ref()?.downcast_ref()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for CustomData {
+    fn eq(&self, other: &Self) -> bool {
+        self.info_type == other.info_type && self.bytes == other.bytes
+    }
+}
+
+impl CustomData {
+    /// The whole of `bytes`, decoded if a payload is registered for `info_type` and fails if that
+    /// doesn't decode them.
+    pub(crate) fn from_bytes(info_type: u8, bytes: &[u8]) -> Option<Self> {
+        let size = u16::try_from(bytes.len()).ok()?;
+        parse_custom(bytes, info_type, size)
+            .ok()
+            .map(|(_, custom)| custom)
+    }
+}
+
+/// `size` bytes, decoded too when a payload is registered for `info_type`.
+pub(crate) fn parse_custom(i: &[u8], info_type: u8, size: u16) -> Res<&[u8], CustomData> {
+    let decoder = REGISTERED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&info_type)
+        .copied();
+    let (i, bytes) = take(size)(i)?;
+    let value = match decoder {
+        Some(decoder) => {
+            let decode = complete(|bytes| decoder(bytes, size));
+            Some(context("Custom Payload", decode)(bytes)?.1)
+        }
+        None => None,
+    };
+    Ok((
+        i,
+        CustomData {
+            info_type,
+            bytes: bytes.to_vec(),
+            value,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_custom_tests {
+    use nom::{
+        error::{
+            ErrorKind, VerboseError,
+            VerboseErrorKind::{Context, Nom},
+        },
+        number::complete::u8,
+        Err::Failure,
+    };
+
+    use super::*;
+    use crate::{character::character_data::CharacterData, decode_jdcp, message::Message};
+
+    struct Sanity;
+
+    impl JdcpPayload for Sanity {
+        type Data = u8;
+
+        const INFO_TYPE: InfoType = InfoType::Custom(0x80);
+        const SIZE: Option<u16> = Some(1);
+
+        fn decode(i: &[u8], _: u16) -> Res<&[u8], u8> {
+            u8(i)
+        }
+
+        fn encode(sanity: &u8) -> Vec<u8> {
+            vec![*sanity]
+        }
+    }
+
+    struct HomebrewHp;
+
+    impl JdcpPayload for HomebrewHp {
+        type Data = u8;
+
+        const INFO_TYPE: InfoType = InfoType::HP;
+        const SIZE: Option<u16> = Some(1);
+
+        fn decode(i: &[u8], _: u16) -> Res<&[u8], u8> {
+            u8(i)
+        }
+
+        fn encode(hp: &u8) -> Vec<u8> {
+            vec![*hp]
+        }
+    }
+
+    fn register_sanity() {
+        match register_payload::<Sanity>() {
+            Ok(()) | Err(CustomPayloadError::AlreadyRegistered(0x80)) => (),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn registered_payloads_decode_to_their_type() {
+        register_sanity();
+        let (rest, message) = decode_jdcp(&b"jdcp-\xBBBart\x00\x80\x01\x00\x2A"[..]).unwrap();
+        assert_eq!(rest, b"");
+        assert_eq!(message.info_type, InfoType::Custom(0x80));
+        let Some(CharacterData::Custom(custom)) = &message.data else {
+            panic!("{:?}", message);
+        };
+        assert_eq!(custom.get::<Sanity>(), Some(&42));
+        assert_eq!(custom.bytes, [42]);
+
+        let sent = CustomData::new::<Sanity>(42).unwrap();
+        assert_eq!(&sent, custom);
+        assert_eq!(sent.get::<Sanity>(), Some(&42));
+        assert_eq!(
+            decode_jdcp(&b"jdcp-\xBBBart\x00\x80\x02\x00\x2A\x2A"[..]).map(|_| ()),
+            Err(Failure(VerboseError {
+                errors: vec![
+                    (&b"\x2A"[..], Nom(ErrorKind::Eof)),
+                    (&b"\x2A\x2A"[..], Context("Custom Payload")),
+                    (&b"\x02\x00\x2A\x2A"[..], Context("Custom Response")),
+                    (&b"\x80\x02\x00\x2A\x2A"[..], Context("Parse Response")),
+                ]
+            }))
+        );
+    }
+
+    #[test]
+    fn unregistered_payloads_keep_their_bytes() {
+        let bytes = b"jdcp-\xCCBart\x00\xFE\x03\x00\x01\x02\x03";
+        let (_, message) = decode_jdcp(&bytes[..]).unwrap();
+        assert_eq!(
+            message.data,
+            Some(CharacterData::Custom(CustomData::raw(0xFE, vec![1, 2, 3])))
+        );
+        assert_eq!(message.encode_jdcp(), bytes);
+        assert_eq!(message.to_string(), "WRITE Bart 254 010203");
+        assert_eq!("WRITE Bart 254 010203".parse::<Message>(), Ok(message));
+        assert!(InfoType::try_from(0xFF).is_err());
+    }
+
+    #[test]
+    fn only_custom_info_types_register() {
+        register_sanity();
+        assert_eq!(
+            register_payload::<Sanity>(),
+            Err(CustomPayloadError::AlreadyRegistered(0x80))
+        );
+        assert_eq!(
+            register_payload::<HomebrewHp>(),
+            Err(CustomPayloadError::NotCustom(InfoType::HP))
+        );
+        assert_eq!(CustomData::new::<HomebrewHp>(3), None);
+        assert!(!unregister_payload::<HomebrewHp>());
+    }
+
+    #[test]
+    fn unregistered_payloads_can_be_replaced() {
+        struct Faith;
+
+        impl JdcpPayload for Faith {
+            type Data = u8;
+
+            const INFO_TYPE: InfoType = InfoType::Custom(0x81);
+            const SIZE: Option<u16> = Some(1);
+
+            fn decode(i: &[u8], _: u16) -> Res<&[u8], u8> {
+                u8(i)
+            }
+
+            fn encode(faith: &u8) -> Vec<u8> {
+                vec![*faith]
+            }
+        }
+
+        let bytes = &b"jdcp-\xBBBart\x00\x81\x01\x00\x07"[..];
+        assert_eq!(register_payload::<Faith>(), Ok(()));
+        let (_, message) = decode_jdcp(bytes).unwrap();
+        let Some(CharacterData::Custom(custom)) = &message.data else {
+            panic!("{:?}", message);
+        };
+        assert_eq!(custom.get::<Faith>(), Some(&7));
+
+        assert!(unregister_payload::<Faith>());
+        assert!(!unregister_payload::<Faith>());
+        let (_, message) = decode_jdcp(bytes).unwrap();
+        assert_eq!(
+            message.data,
+            Some(CharacterData::Custom(CustomData::raw(0x81, vec![7])))
+        );
+        assert_eq!(register_payload::<Faith>(), Ok(()));
+        assert!(unregister_payload::<Faith>());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Custom(130) encoded")]
+    fn encoded_sizes_are_checked() {
+        struct Wrong;
+
+        impl JdcpPayload for Wrong {
+            type Data = u8;
+
+            const INFO_TYPE: InfoType = InfoType::Custom(0x82);
+            const SIZE: Option<u16> = Some(2);
+
+            fn decode(i: &[u8], _: u16) -> Res<&[u8], u8> {
+                u8(i)
+            }
+
+            fn encode(wrong: &u8) -> Vec<u8> {
+                vec![*wrong]
+            }
+        }
+
+        CustomData::new::<Wrong>(1);
+    }
+}
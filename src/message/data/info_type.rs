@@ -1,40 +1,38 @@
-use nom::{bytes::streaming::take, error::context};
+use nom::{
+    combinator::{map, map_res},
+    error::context,
+    number::streaming::u8,
+};
 
 use crate::Res;
 
-#[derive(Debug, PartialEq)]
-#[repr(u8)]
-pub enum InfoType {
-    STATS = 1,
-    AGE = 2,
-    CLASS = 3,
-    RACE = 4,
-    LEVEL = 5,
-    HP = 6,
-}
+pub use super::payload::InfoType;
 
 impl InfoType {
-    pub fn discriminant(&self) -> u8 {
-        unsafe { *(self as *const Self as *const u8) }
-    }
-}
-
-impl From<&[u8]> for InfoType {
-    fn from(value: &[u8]) -> Self {
-        match value.first() {
-            Some(1) => InfoType::STATS,
-            Some(2) => InfoType::AGE,
-            Some(3) => InfoType::CLASS,
-            Some(4) => InfoType::RACE,
-            Some(5) => InfoType::LEVEL,
-            Some(6) => InfoType::HP,
-            _ => unimplemented!("No other info_types currently"),
+    /// The readable info type whose value a write of this info type changes.
+    pub fn readable(&self) -> InfoType {
+        match self {
+            InfoType::CASTSPELL | InfoType::REST => InfoType::SPELLSLOTS,
+            InfoType::ADDCONDITIONS | InfoType::REMOVECONDITIONS => InfoType::CONDITIONS,
+            InfoType::AWARDXP => InfoType::XP,
+            InfoType::ROLL => InfoType::ROLLRESULT,
+            other => *other,
         }
     }
 }
 
 pub fn parse_info_type(i: &[u8]) -> Res<&[u8], InfoType> {
-    context("info_type", take(1u8))(i).map(|(i, result)| (i, result.into()))
+    context("info_type", map_res(u8, InfoType::try_from))(i)
+}
+
+/// Reads any byte, keeping ones no info type uses as [`InfoType::Unknown`].
+pub fn parse_any_info_type(i: &[u8]) -> Res<&[u8], InfoType> {
+    context(
+        "info_type",
+        map(u8, |byte| {
+            InfoType::try_from(byte).unwrap_or(InfoType::Unknown(byte))
+        }),
+    )(i)
 }
 
 #[cfg(test)]
@@ -49,6 +47,20 @@ mod josh_dnd_character_protocol_info_type_tests {
         let race = parse_info_type(&b"\x04\xAA"[..]);
         let level = parse_info_type(&b"\x05\xAA"[..]);
         let hp = parse_info_type(&b"\x06\xAA"[..]);
+        let spell_slots = parse_info_type(&b"\x07\xAA"[..]);
+        let spells = parse_info_type(&b"\x08\xAA"[..]);
+        let cast_spell = parse_info_type(&b"\x09\xAA"[..]);
+        let rest = parse_info_type(&b"\x0A\xAA"[..]);
+        let conditions = parse_info_type(&b"\x0B\xAA"[..]);
+        let add_conditions = parse_info_type(&b"\x0C\xAA"[..]);
+        let remove_conditions = parse_info_type(&b"\x0D\xAA"[..]);
+        let skills = parse_info_type(&b"\x0E\xAA"[..]);
+        let combat = parse_info_type(&b"\x0F\xAA"[..]);
+        let xp = parse_info_type(&b"\x10\xAA"[..]);
+        let award_xp = parse_info_type(&b"\x11\xAA"[..]);
+        let roll = parse_info_type(&b"\x12\xAA"[..]);
+        let roll_result = parse_info_type(&b"\x13\xAA"[..]);
+        let roll_log = parse_info_type(&b"\x14\xAA"[..]);
 
         assert_eq!(stats, Ok((&b"\xAA"[..], InfoType::STATS)));
         assert_eq!(age, Ok((&b"\xAA"[..], InfoType::AGE)));
@@ -56,5 +68,22 @@ mod josh_dnd_character_protocol_info_type_tests {
         assert_eq!(race, Ok((&b"\xAA"[..], InfoType::RACE)));
         assert_eq!(level, Ok((&b"\xAA"[..], InfoType::LEVEL)));
         assert_eq!(hp, Ok((&b"\xAA"[..], InfoType::HP)));
+        assert_eq!(spell_slots, Ok((&b"\xAA"[..], InfoType::SPELLSLOTS)));
+        assert_eq!(spells, Ok((&b"\xAA"[..], InfoType::SPELLS)));
+        assert_eq!(cast_spell, Ok((&b"\xAA"[..], InfoType::CASTSPELL)));
+        assert_eq!(rest, Ok((&b"\xAA"[..], InfoType::REST)));
+        assert_eq!(conditions, Ok((&b"\xAA"[..], InfoType::CONDITIONS)));
+        assert_eq!(add_conditions, Ok((&b"\xAA"[..], InfoType::ADDCONDITIONS)));
+        assert_eq!(
+            remove_conditions,
+            Ok((&b"\xAA"[..], InfoType::REMOVECONDITIONS))
+        );
+        assert_eq!(skills, Ok((&b"\xAA"[..], InfoType::SKILLS)));
+        assert_eq!(combat, Ok((&b"\xAA"[..], InfoType::COMBAT)));
+        assert_eq!(xp, Ok((&b"\xAA"[..], InfoType::XP)));
+        assert_eq!(award_xp, Ok((&b"\xAA"[..], InfoType::AWARDXP)));
+        assert_eq!(roll, Ok((&b"\xAA"[..], InfoType::ROLL)));
+        assert_eq!(roll_result, Ok((&b"\xAA"[..], InfoType::ROLLRESULT)));
+        assert_eq!(roll_log, Ok((&b"\xAA"[..], InfoType::ROLLLOG)));
     }
 }
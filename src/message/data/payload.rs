@@ -0,0 +1,336 @@
+//! One row per info type: its byte, the name errors use, the data it carries, its data size and
+//! how the data is decoded and encoded. The table produces [`InfoType`], [`CharacterData`], a
+//! [`JdcpPayload`] marker per info type (e.g. [`HP`]) and the dispatch the response parser uses,
+//! so adding an info type is one new row. Vendor info types are [`InfoType::Custom`], see
+//! [`super::custom`].
+
+use nom::bytes::streaming::take;
+
+use super::{
+    character_data::{
+        parse_age, parse_cast_spell, parse_class, parse_combat, parse_conditions, parse_hp,
+        parse_level, parse_race, parse_rest, parse_roll, parse_roll_log, parse_roll_result,
+        parse_skills, parse_spell_slots, parse_spells, parse_stats, parse_xp,
+    },
+    custom::{parse_custom, CustomData, CUSTOM_INFO_TYPES},
+};
+use crate::{
+    character::character_data::{
+        class_type::ClassType,
+        combat_stats::CombatStats,
+        conditions::Conditions,
+        health_points::HealthPoints,
+        race_kind::RaceKind,
+        rest_kind::RestKind,
+        roll_record::{RollLogEntry, RollRecord},
+        skill_proficiencies::SkillProficiencies,
+        spell_slots::SpellSlots,
+        stat_block::StatBlock,
+    },
+    Res,
+};
+
+/// The data one info type carries and how it goes on and off the wire.
+pub trait JdcpPayload {
+    type Data;
+
+    const INFO_TYPE: InfoType;
+    /// The data size every message of this info type has, or `None` when it's sized by its data.
+    const SIZE: Option<u16>;
+
+    /// Reads the payload, `size` bytes long, that follows a message's data size.
+    fn decode(i: &[u8], size: u16) -> Res<&[u8], Self::Data>;
+    fn encode(data: &Self::Data) -> Vec<u8>;
+}
+
+macro_rules! payload_size {
+    (fixed $size:literal) => {
+        Some($size)
+    };
+    (sized) => {
+        None
+    };
+}
+
+macro_rules! payload_size_context {
+    (fixed $size:literal) => {
+        Some(concat!("Data Size ", $size))
+    };
+    (sized) => {
+        None
+    };
+}
+
+macro_rules! payloads {
+    ($(
+        $variant:ident = $byte:literal, $name:literal: $data:ty, $kind:ident $($size:literal)?,
+            decode($i:ident, $data_size:tt) => $decode:expr,
+            encode($value:ident) => $encode:expr;
+    )+) => {
+        #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(remote = "Self")
+        )]
+        pub enum InfoType {
+            $($variant,)+
+            /// A vendor info type from [`CUSTOM_INFO_TYPES`].
+            Custom(u8),
+            /// A byte no info type uses, only decoded in [`super::DecodeMode::LENIENT`].
+            Unknown(u8),
+        }
+
+        impl InfoType {
+            /// The built in info types.
+            pub const ALL: [InfoType; [$($byte),+].len()] = [$(InfoType::$variant),+];
+
+            pub fn discriminant(&self) -> u8 {
+                match self {
+                    $(InfoType::$variant => $byte,)+
+                    InfoType::Custom(info_type) | InfoType::Unknown(info_type) => *info_type,
+                }
+            }
+        }
+
+        /// Fails with the byte when no info type uses it.
+        impl TryFrom<u8> for InfoType {
+            type Error = u8;
+
+            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                match value {
+                    $($byte => Ok(InfoType::$variant),)+
+                    custom if CUSTOM_INFO_TYPES.contains(&custom) => Ok(InfoType::Custom(custom)),
+                    other => Err(other),
+                }
+            }
+        }
+
+        #[derive(Debug, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum CharacterData {
+            $($variant($data),)+
+            Custom(CustomData),
+            /// The payload of an [`InfoType::Unknown`], as it was sent.
+            Unknown { info_type: u8, bytes: Vec<u8> },
+        }
+
+        impl CharacterData {
+            pub fn info_type(&self) -> InfoType {
+                match self {
+                    $(CharacterData::$variant(_) => InfoType::$variant,)+
+                    CharacterData::Custom(custom) => InfoType::Custom(custom.info_type),
+                    CharacterData::Unknown { info_type, .. } => InfoType::Unknown(*info_type),
+                }
+            }
+
+            /// Number of bytes the data takes up on the wire, i.e. the message `data_size`, or
+            /// `None` when that's more than a data size can hold.
+            pub fn size(&self) -> Option<u16> {
+                match self {
+                    $(CharacterData::$variant(value) => match <$variant as JdcpPayload>::SIZE {
+                        Some(size) => Some(size),
+                        None => u16::try_from($variant::encode(value).len()).ok(),
+                    },)+
+                    CharacterData::Custom(custom) => u16::try_from(custom.bytes.len()).ok(),
+                    CharacterData::Unknown { bytes, .. } => u16::try_from(bytes.len()).ok(),
+                }
+            }
+
+            pub fn to_bytes(&self) -> Vec<u8> {
+                match self {
+                    $(CharacterData::$variant(value) => $variant::encode(value),)+
+                    CharacterData::Custom(custom) => custom.bytes.clone(),
+                    CharacterData::Unknown { bytes, .. } => bytes.clone(),
+                }
+            }
+        }
+
+        $(
+            #[doc = concat!("The ", $name, " payload.")]
+            #[allow(clippy::upper_case_acronyms)]
+            pub struct $variant;
+
+            impl JdcpPayload for $variant {
+                type Data = $data;
+
+                const INFO_TYPE: InfoType = InfoType::$variant;
+                const SIZE: Option<u16> = payload_size!($kind $($size)?);
+
+                fn decode($i: &[u8], $data_size: u16) -> Res<&[u8], Self::Data> {
+                    $decode
+                }
+
+                fn encode($value: &Self::Data) -> Vec<u8> {
+                    $encode
+                }
+            }
+        )+
+
+        /// How a response is named in errors, and its fixed data size with the context naming it.
+        pub(super) fn response_layout(
+            info_type: InfoType,
+        ) -> (&'static str, Option<(u16, &'static str)>) {
+            match info_type {
+                $(InfoType::$variant => (
+                    concat!($name, " Response"),
+                    <$variant as JdcpPayload>::SIZE.zip(payload_size_context!($kind $($size)?)),
+                ),)+
+                InfoType::Custom(_) => ("Custom Response", None),
+                InfoType::Unknown(_) => ("Unknown Response", None),
+            }
+        }
+
+        pub(super) fn parse_payload(
+            i: &[u8],
+            info_type: InfoType,
+            size: u16,
+        ) -> Res<&[u8], CharacterData> {
+            match info_type {
+                $(InfoType::$variant => {
+                    $variant::decode(i, size).map(|(i, data)| (i, CharacterData::$variant(data)))
+                })+
+                InfoType::Custom(info_type) => {
+                    parse_custom(i, info_type, size).map(|(i, data)| (i, CharacterData::Custom(data)))
+                }
+                InfoType::Unknown(info_type) => take(size)(i).map(|(i, bytes): (_, &[u8])| {
+                    (
+                        i,
+                        CharacterData::Unknown {
+                            info_type,
+                            bytes: bytes.to_vec(),
+                        },
+                    )
+                }),
+            }
+        }
+    };
+}
+
+payloads! {
+    STATS = 1, "Stats": StatBlock, fixed 6,
+        decode(i, _) => parse_stats(i),
+        encode(stats) => stats.to_bytes();
+    AGE = 2, "Age": u16, fixed 2,
+        decode(i, _) => parse_age(i),
+        encode(age) => age.to_le_bytes().to_vec();
+    CLASS = 3, "Class": ClassType, fixed 1,
+        decode(i, _) => parse_class(i),
+        encode(class) => vec![class.discriminant()];
+    RACE = 4, "Race": RaceKind, fixed 1,
+        decode(i, _) => parse_race(i),
+        encode(race) => vec![race.discriminant()];
+    LEVEL = 5, "Level": u8, fixed 1,
+        decode(i, _) => parse_level(i),
+        encode(level) => vec![*level];
+    HP = 6, "HP": HealthPoints, fixed 2,
+        decode(i, _) => parse_hp(i),
+        encode(hp) => vec![hp.current, hp.max];
+    SPELLSLOTS = 7, "Spell Slots": SpellSlots, fixed 21,
+        decode(i, _) => parse_spell_slots(i),
+        encode(slots) => slots.to_bytes();
+    SPELLS = 8, "Spells": Vec<String>, sized,
+        decode(i, size) => parse_spells(i, size),
+        encode(spells) => spells
+            .iter()
+            .flat_map(|spell| [spell.as_bytes(), b"\x00"].concat())
+            .collect();
+    CASTSPELL = 9, "Cast Spell": u8, fixed 1,
+        decode(i, _) => parse_cast_spell(i),
+        encode(slot_level) => vec![*slot_level];
+    REST = 10, "Rest": RestKind, fixed 1,
+        decode(i, _) => parse_rest(i),
+        encode(rest) => vec![rest.discriminant()];
+    CONDITIONS = 11, "Conditions": Conditions, fixed 3,
+        decode(i, _) => parse_conditions(i),
+        encode(conditions) => conditions.to_bytes();
+    ADDCONDITIONS = 12, "Add Conditions": Conditions, fixed 3,
+        decode(i, _) => parse_conditions(i),
+        encode(conditions) => conditions.to_bytes();
+    REMOVECONDITIONS = 13, "Remove Conditions": Conditions, fixed 3,
+        decode(i, _) => parse_conditions(i),
+        encode(conditions) => conditions.to_bytes();
+    SKILLS = 14, "Skills": SkillProficiencies, fixed 6,
+        decode(i, _) => parse_skills(i),
+        encode(skills) => skills.to_bytes();
+    COMBAT = 15, "Combat": CombatStats, fixed 3,
+        decode(i, _) => parse_combat(i),
+        encode(combat) => combat.to_bytes();
+    XP = 16, "XP": u32, fixed 4,
+        decode(i, _) => parse_xp(i),
+        encode(xp) => xp.to_le_bytes().to_vec();
+    AWARDXP = 17, "Award XP": u32, fixed 4,
+        decode(i, _) => parse_xp(i),
+        encode(xp) => xp.to_le_bytes().to_vec();
+    ROLL = 18, "Roll": String, sized,
+        decode(i, size) => parse_roll(i, size),
+        encode(expression) => expression.as_bytes().to_vec();
+    ROLLRESULT = 19, "Roll Result": RollRecord, sized,
+        decode(i, size) => parse_roll_result(i, size),
+        encode(record) => record.to_bytes();
+    ROLLLOG = 20, "Roll Log": Vec<RollLogEntry>, sized,
+        decode(i, size) => parse_roll_log(i, size),
+        encode(entries) => entries.iter().flat_map(RollLogEntry::to_bytes).collect();
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_payload_tests {
+    use super::*;
+
+    #[test]
+    fn fixed_sizes_match_what_is_encoded() {
+        assert_eq!(<SPELLSLOTS as JdcpPayload>::SIZE, Some(SpellSlots::SIZE));
+        assert_eq!(<CONDITIONS as JdcpPayload>::SIZE, Some(Conditions::SIZE));
+        assert_eq!(
+            <SKILLS as JdcpPayload>::SIZE,
+            Some(SkillProficiencies::SIZE)
+        );
+        assert_eq!(<COMBAT as JdcpPayload>::SIZE, Some(CombatStats::SIZE));
+        assert_eq!(
+            STATS::encode(&StatBlock::from(&[8, 12, 19, 14, 16, 9][..])).len(),
+            6
+        );
+    }
+
+    #[test]
+    fn payloads_decode_what_they_encode() {
+        let hp = HealthPoints {
+            current: 34,
+            max: 41,
+        };
+        let encoded = HP::encode(&hp);
+        assert_eq!(HP::decode(&encoded, 2), Ok((&b""[..], hp)));
+        assert_eq!(<HP as JdcpPayload>::INFO_TYPE, InfoType::HP);
+
+        let spells = vec!["Shield".to_string(), "Sleep".to_string()];
+        let encoded = SPELLS::encode(&spells);
+        assert_eq!(
+            SPELLS::decode(&encoded, encoded.len() as u16),
+            Ok((&b""[..], spells))
+        );
+    }
+
+    #[test]
+    fn oversized_data_has_no_size() {
+        assert_eq!(CharacterData::ROLL("1d6".to_string()).size(), Some(3));
+        assert_eq!(CharacterData::ROLL("1".repeat(65535)).size(), Some(65535));
+        assert_eq!(CharacterData::ROLL("1".repeat(65536)).size(), None);
+        assert_eq!(
+            CharacterData::Unknown {
+                info_type: 0xFF,
+                bytes: vec![0; 70000]
+            }
+            .size(),
+            None
+        );
+    }
+
+    #[test]
+    fn every_info_type_has_its_byte() {
+        for info_type in InfoType::ALL {
+            assert_eq!(InfoType::try_from(info_type.discriminant()), Ok(info_type));
+        }
+        assert_eq!(InfoType::try_from(0), Err(0));
+        assert_eq!(InfoType::try_from(21), Err(21));
+    }
+}
@@ -1,36 +1,56 @@
 use nom::{
-    bytes::streaming::{tag, take},
-    error::context,
+    bytes::streaming::tag, combinator::map_res, error::context, number::streaming::u8,
     sequence::preceded,
 };
 
 use crate::Res;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(remote = "Self")
+)]
 #[repr(u8)]
 pub enum MessageType {
     REQUEST = 0xAA,
     RESPONSE = 0xBB,
+    WRITE = 0xCC,
+    SUBSCRIBE = 0xDD,
 }
 
 impl MessageType {
+    pub const ALL: [MessageType; 4] = [
+        MessageType::REQUEST,
+        MessageType::RESPONSE,
+        MessageType::WRITE,
+        MessageType::SUBSCRIBE,
+    ];
+
     pub fn discriminant(&self) -> u8 {
         unsafe { *(self as *const Self as *const u8) }
     }
 }
 
-impl From<&[u8]> for MessageType {
-    fn from(value: &[u8]) -> Self {
-        match value.first() {
-            Some(0xAA) => MessageType::REQUEST,
-            Some(0xBB) => MessageType::RESPONSE,
-            _ => unimplemented!("No other messages currently"),
+/// Fails with the byte when no message type uses it.
+impl TryFrom<u8> for MessageType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0xAA => Ok(MessageType::REQUEST),
+            0xBB => Ok(MessageType::RESPONSE),
+            0xCC => Ok(MessageType::WRITE),
+            0xDD => Ok(MessageType::SUBSCRIBE),
+            other => Err(other),
         }
     }
 }
 pub fn parse_message_type(i: &[u8]) -> Res<&[u8], MessageType> {
-    context("message_type", preceded(tag("jdcp-"), take(1u8)))(i)
-        .map(|(i, result)| (i, result.into()))
+    context(
+        "message_type",
+        preceded(tag("jdcp-"), map_res(u8, MessageType::try_from)),
+    )(i)
 }
 
 #[cfg(test)]
@@ -40,7 +60,22 @@ mod josh_dnd_character_protocol_message_type_tests {
     fn message_type_byte_returns_correct_type() {
         let request_message = parse_message_type(&b"jdcp-\xAA\x12"[..]);
         let response_message = parse_message_type(&b"jdcp-\xBB\x12"[..]);
+        let write_message = parse_message_type(&b"jdcp-\xCC\x12"[..]);
+        let subscribe_message = parse_message_type(&b"jdcp-\xDD\x12"[..]);
         assert_eq!(request_message, Ok((&b"\x12"[..], MessageType::REQUEST)));
         assert_eq!(response_message, Ok((&b"\x12"[..], MessageType::RESPONSE)));
+        assert_eq!(write_message, Ok((&b"\x12"[..], MessageType::WRITE)));
+        assert_eq!(
+            subscribe_message,
+            Ok((&b"\x12"[..], MessageType::SUBSCRIBE))
+        );
+    }
+
+    #[test]
+    fn unknown_message_type_byte_is_an_error() {
+        assert!(matches!(
+            parse_message_type(&b"jdcp-\x00\x12"[..]),
+            Err(nom::Err::Error(_))
+        ));
     }
 }
@@ -0,0 +1,647 @@
+use std::{fmt, str::FromStr};
+
+use bitflags::Flags;
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped_transform, is_not, tag, take_until},
+    character::complete::{alpha1, char, digit1, one_of},
+    combinator::{all_consuming, map, map_opt, map_res, opt, recognize, rest, value, verify},
+    error::{context, convert_error, VerboseError},
+    multi::{many0, separated_list0, separated_list1},
+    sequence::{pair, preceded, separated_pair, tuple},
+    IResult,
+};
+
+use super::{
+    custom::{CustomData, CUSTOM_INFO_TYPES},
+    InfoType, Message, MessageType,
+};
+use crate::{
+    character::character_data::{
+        class_type::ClassType,
+        combat_stats::CombatStats,
+        conditions::{ConditionFlags, Conditions},
+        health_points::HealthPoints,
+        race_kind::RaceKind,
+        rest_kind::RestKind,
+        roll_record::{RollLogEntry, RollRecord},
+        skill_proficiencies::{SkillFlags, SkillProficiencies},
+        spell_slots::{PactSlots, SpellSlots},
+        stat_block::StatBlock,
+        CharacterData,
+    },
+    dice::{DieResult, RollResult},
+};
+
+type TextRes<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+/// Why a line of text isn't a message, pointing at where parsing stopped.
+#[derive(Debug, PartialEq)]
+pub struct ParseMessageError(String);
+
+impl fmt::Display for ParseMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMessageError {}
+
+fn flag_names<F: Flags>(flags: &F) -> String {
+    let names: Vec<&str> = flags.iter_names().map(|(name, _)| name).collect();
+    if names.is_empty() {
+        "NONE".to_string()
+    } else {
+        names.join("|")
+    }
+}
+
+fn class_text(class: &ClassType) -> String {
+    match class {
+        ClassType::Unknown(id) => id.to_string(),
+        known => format!("{:?}", known),
+    }
+}
+
+fn race_text(race: &RaceKind) -> String {
+    match race {
+        RaceKind::Unknown(id) => id.to_string(),
+        known => format!("{:?}", known),
+    }
+}
+
+fn info_type_text(info_type: &InfoType) -> String {
+    match info_type {
+        InfoType::Custom(id) | InfoType::Unknown(id) => id.to_string(),
+        known => format!("{:?}", known),
+    }
+}
+
+fn roll_record_text(record: &RollRecord) -> String {
+    let mut text = format!(
+        "{} => {} {:+}",
+        record.expression, record.result.total, record.result.modifier
+    );
+    for die in &record.result.dice {
+        text.push_str(&format!(
+            " d{}:{}{}",
+            die.sides,
+            die.value,
+            if die.kept { "" } else { "x" }
+        ));
+    }
+    text
+}
+
+/// Text form of a payload, e.g. `34/42` for HP.
+pub(crate) fn data_text(data: &CharacterData) -> String {
+    match data {
+        CharacterData::STATS(stats) => format!(
+            "{} {} {} {} {} {}",
+            stats.strength,
+            stats.dexterity,
+            stats.constitution,
+            stats.intelligence,
+            stats.wisdom,
+            stats.charisma
+        ),
+        CharacterData::AGE(age) => age.to_string(),
+        CharacterData::CLASS(class) => class_text(class),
+        CharacterData::RACE(race) => race_text(race),
+        CharacterData::LEVEL(level) | CharacterData::CASTSPELL(level) => level.to_string(),
+        CharacterData::HP(hp) => format!("{}/{}", hp.current, hp.max),
+        CharacterData::SPELLSLOTS(slots) => {
+            let mut levels: Vec<String> = (0..9)
+                .filter(|level| slots.max[*level] > 0 || slots.used[*level] > 0)
+                .map(|level| format!("{}:{}/{}", level + 1, slots.used[level], slots.max[level]))
+                .collect();
+            if slots.pact != PactSlots::default() {
+                levels.push(format!(
+                    "pact:{}:{}/{}",
+                    slots.pact.level, slots.pact.used, slots.pact.max
+                ));
+            }
+            levels.join(" ")
+        }
+        CharacterData::SPELLS(spells) => spells
+            .iter()
+            .map(|spell| spell.replace('\\', "\\\\").replace(',', "\\,"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        CharacterData::REST(rest) => format!("{:?}", rest),
+        CharacterData::CONDITIONS(conditions)
+        | CharacterData::ADDCONDITIONS(conditions)
+        | CharacterData::REMOVECONDITIONS(conditions) => {
+            format!(
+                "{} {}",
+                flag_names(&conditions.flags),
+                conditions.exhaustion
+            )
+        }
+        CharacterData::SKILLS(skills) => format!(
+            "{} {}",
+            flag_names(&skills.proficient),
+            flag_names(&skills.expertise)
+        ),
+        CharacterData::COMBAT(combat) => format!(
+            "{} {} {}",
+            combat.armor_class, combat.initiative, combat.speed
+        ),
+        CharacterData::XP(xp) | CharacterData::AWARDXP(xp) => xp.to_string(),
+        CharacterData::ROLL(expression) => expression.clone(),
+        CharacterData::ROLLRESULT(record) => roll_record_text(record),
+        CharacterData::ROLLLOG(entries) => entries
+            .iter()
+            .map(|entry| format!("{}: {}", entry.roller, roll_record_text(&entry.record)))
+            .collect::<Vec<String>>()
+            .join("; "),
+        CharacterData::Custom(custom) => hex::encode(&custom.bytes),
+        CharacterData::Unknown { bytes, .. } => hex::encode(bytes),
+    }
+}
+
+/// `MESSAGETYPE name INFOTYPE [data]`, e.g. `RESPONSE Bart HP 34/42` or `REQUEST Bart STATS`.
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} {}",
+            self.message_type,
+            self.character_name,
+            info_type_text(&self.info_type)
+        )?;
+        match self.data.as_ref().map(data_text) {
+            Some(data) if !data.is_empty() => write!(f, " {}", data),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn number<T: FromStr>(i: &str) -> TextRes<'_, T> {
+    map_res(digit1, str::parse)(i)
+}
+
+fn signed<T: FromStr>(i: &str) -> TextRes<'_, T> {
+    map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse)(i)
+}
+
+fn flags<F: Flags>(i: &str) -> TextRes<'_, F> {
+    alt((
+        map(tag("NONE"), |_| F::empty()),
+        map(
+            separated_list1(char('|'), map_opt(alpha1, F::from_name)),
+            |flags| {
+                F::from_bits_retain(
+                    flags
+                        .iter()
+                        .fold(F::empty().bits(), |bits, flag| bits | flag.bits()),
+                )
+            },
+        ),
+    ))(i)
+}
+
+fn named<T: fmt::Debug + Clone>(all: &'static [T]) -> impl FnMut(&str) -> TextRes<'_, T> {
+    move |i| {
+        map_opt(alpha1, |name: &str| {
+            all.iter()
+                .find(|known| format!("{:?}", known) == name)
+                .cloned()
+        })(i)
+    }
+}
+
+fn stats(i: &str) -> TextRes<'_, StatBlock> {
+    map(
+        tuple((
+            number,
+            preceded(char(' '), number),
+            preceded(char(' '), number),
+            preceded(char(' '), number),
+            preceded(char(' '), number),
+            preceded(char(' '), number),
+        )),
+        |(str, dex, con, int, wis, cha)| StatBlock::new(str, dex, con, int, wis, cha),
+    )(i)
+}
+
+fn slot(i: &str) -> TextRes<'_, (u8, u8, u8)> {
+    tuple((
+        number,
+        preceded(char(':'), number),
+        preceded(char('/'), number),
+    ))(i)
+}
+
+fn spell_slots(i: &str) -> TextRes<'_, SpellSlots> {
+    map(
+        separated_list0(
+            char(' '),
+            alt((
+                map(preceded(tag("pact:"), slot), |pact| (true, pact)),
+                map(
+                    verify(slot, |(level, _, _)| (1..=9).contains(level)),
+                    |slot| (false, slot),
+                ),
+            )),
+        ),
+        |levels| {
+            let mut slots = SpellSlots::default();
+            for (pact, (level, used, max)) in levels {
+                if pact {
+                    slots.pact = PactSlots { level, max, used };
+                } else {
+                    slots.used[usize::from(level - 1)] = used;
+                    slots.max[usize::from(level - 1)] = max;
+                }
+            }
+            slots
+        },
+    )(i)
+}
+
+/// A spell name, with `\,` standing for a comma and `\\` for a backslash.
+fn spell_name(i: &str) -> TextRes<'_, String> {
+    verify(
+        escaped_transform(
+            is_not("\\,"),
+            '\\',
+            alt((value("\\", char('\\')), value(",", char(',')))),
+        ),
+        |name: &String| !name.is_empty(),
+    )(i)
+}
+
+fn conditions(i: &str) -> TextRes<'_, Conditions> {
+    map(
+        separated_pair(
+            context("condition flags", flags::<ConditionFlags>),
+            char(' '),
+            context("exhaustion", number),
+        ),
+        |(flags, exhaustion)| Conditions::new(flags, exhaustion),
+    )(i)
+}
+
+fn roll_record(i: &str) -> TextRes<'_, RollRecord> {
+    let die = map(
+        tuple((
+            preceded(char('d'), number),
+            preceded(char(':'), number),
+            opt(char('x')),
+        )),
+        |(sides, value, dropped)| DieResult {
+            sides,
+            value,
+            kept: dropped.is_none(),
+        },
+    );
+    map(
+        tuple((
+            take_until(" => "),
+            preceded(tag(" => "), signed),
+            preceded(char(' '), signed),
+            many0(preceded(char(' '), die)),
+        )),
+        |(expression, total, modifier, dice)| RollRecord {
+            expression: expression.to_string(),
+            result: RollResult {
+                dice,
+                modifier,
+                total,
+            },
+        },
+    )(i)
+}
+
+fn data(i: &str, info_type: InfoType) -> TextRes<'_, CharacterData> {
+    match info_type {
+        InfoType::STATS => context("stats", map(stats, CharacterData::STATS))(i),
+        InfoType::AGE => context("age", map(number, CharacterData::AGE))(i),
+        InfoType::CLASS => context(
+            "class",
+            map(
+                alt((
+                    map(number, |id: u8| ClassType::from(&[id][..])),
+                    named(&ClassType::ALL),
+                )),
+                CharacterData::CLASS,
+            ),
+        )(i),
+        InfoType::RACE => context(
+            "race",
+            map(
+                alt((
+                    map(number, |id: u8| RaceKind::from(&[id][..])),
+                    named(&RaceKind::ALL),
+                )),
+                CharacterData::RACE,
+            ),
+        )(i),
+        InfoType::LEVEL => context("level", map(number, CharacterData::LEVEL))(i),
+        InfoType::HP => context(
+            "hp",
+            map(
+                separated_pair(number, char('/'), number),
+                |(current, max)| CharacterData::HP(HealthPoints { current, max }),
+            ),
+        )(i),
+        InfoType::SPELLSLOTS => {
+            context("spell slots", map(spell_slots, CharacterData::SPELLSLOTS))(i)
+        }
+        InfoType::SPELLS => context(
+            "spells",
+            map(
+                separated_list0(tag(", "), spell_name),
+                CharacterData::SPELLS,
+            ),
+        )(i),
+        InfoType::CASTSPELL => context("slot level", map(number, CharacterData::CASTSPELL))(i),
+        InfoType::REST => context(
+            "rest",
+            map(
+                alt((
+                    value(RestKind::SHORT, tag("SHORT")),
+                    value(RestKind::LONG, tag("LONG")),
+                )),
+                CharacterData::REST,
+            ),
+        )(i),
+        InfoType::CONDITIONS => map(conditions, CharacterData::CONDITIONS)(i),
+        InfoType::ADDCONDITIONS => map(conditions, CharacterData::ADDCONDITIONS)(i),
+        InfoType::REMOVECONDITIONS => map(conditions, CharacterData::REMOVECONDITIONS)(i),
+        InfoType::SKILLS => context(
+            "skill flags",
+            map(
+                separated_pair(flags::<SkillFlags>, char(' '), flags::<SkillFlags>),
+                |(proficient, expertise)| {
+                    CharacterData::SKILLS(SkillProficiencies {
+                        proficient,
+                        expertise,
+                    })
+                },
+            ),
+        )(i),
+        InfoType::COMBAT => context(
+            "combat",
+            map(
+                tuple((
+                    number,
+                    preceded(char(' '), signed),
+                    preceded(char(' '), number),
+                )),
+                |(armor_class, initiative, speed)| {
+                    CharacterData::COMBAT(CombatStats {
+                        armor_class,
+                        initiative,
+                        speed,
+                    })
+                },
+            ),
+        )(i),
+        InfoType::XP => context("xp", map(number, CharacterData::XP))(i),
+        InfoType::AWARDXP => context("xp", map(number, CharacterData::AWARDXP))(i),
+        InfoType::ROLL => context(
+            "dice expression",
+            map(
+                verify(rest, |expression: &str| !expression.is_empty()),
+                |expression: &str| CharacterData::ROLL(expression.to_string()),
+            ),
+        )(i),
+        InfoType::ROLLRESULT => {
+            context("roll result", map(roll_record, CharacterData::ROLLRESULT))(i)
+        }
+        InfoType::ROLLLOG => context(
+            "roll log",
+            map(
+                separated_list0(
+                    tag("; "),
+                    map(
+                        separated_pair(alpha1, tag(": "), roll_record),
+                        |(roller, record)| RollLogEntry {
+                            roller: roller.to_string(),
+                            record,
+                        },
+                    ),
+                ),
+                CharacterData::ROLLLOG,
+            ),
+        )(i),
+        InfoType::Custom(id) => context(
+            "custom payload hex",
+            map_opt(map_res(rest, hex::decode), |bytes| {
+                CustomData::from_bytes(id, &bytes).map(CharacterData::Custom)
+            }),
+        )(i),
+        InfoType::Unknown(info_type) => context(
+            "unknown payload hex",
+            map(map_res(rest, hex::decode), |bytes| CharacterData::Unknown {
+                info_type,
+                bytes,
+            }),
+        )(i),
+    }
+}
+
+fn header(i: &str) -> TextRes<'_, (MessageType, &str, InfoType)> {
+    tuple((
+        context("message type", named(&MessageType::ALL)),
+        preceded(char(' '), context("character name", alpha1)),
+        preceded(
+            char(' '),
+            context(
+                "info type",
+                alt((
+                    named(&InfoType::ALL),
+                    map(
+                        verify(number, |id: &u8| CUSTOM_INFO_TYPES.contains(id)),
+                        InfoType::Custom,
+                    ),
+                    map(
+                        verify(number, |id: &u8| InfoType::try_from(*id).is_err()),
+                        InfoType::Unknown,
+                    ),
+                )),
+            ),
+        ),
+    ))(i)
+}
+
+fn message(i: &str) -> TextRes<'_, Message<'static>> {
+    let (i, (message_type, name, info_type)) = header(i)?;
+    let (i, (data_size, data)) = match message_type {
+        MessageType::REQUEST | MessageType::SUBSCRIBE => (i, (0, None)),
+        MessageType::RESPONSE | MessageType::WRITE => {
+            let (i, _) = opt(char(' '))(i)?;
+            context(
+                "data size",
+                map_opt(
+                    |i| data(i, info_type),
+                    |data| Some((data.size()?, Some(data))),
+                ),
+            )(i)?
+        }
+    };
+    Ok((
+        i,
+        Message {
+            message_type,
+            character_name: name.to_string().into(),
+            info_type,
+            data_size,
+            data,
+        },
+    ))
+}
+
+impl FromStr for Message<'_> {
+    type Err = ParseMessageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        all_consuming(message)(s)
+            .map(|(_, message)| message)
+            .map_err(|e| match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => ParseMessageError(convert_error(s, e)),
+                nom::Err::Incomplete(_) => ParseMessageError(format!("Incomplete message {:?}", s)),
+            })
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_text_tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> Message<'static> {
+        let message: Message = text.parse().unwrap();
+        assert_eq!(message.to_string(), text);
+        message
+    }
+
+    #[test]
+    fn requests_and_responses() {
+        assert_eq!(
+            round_trip("REQUEST Bart STATS"),
+            Message {
+                message_type: MessageType::REQUEST,
+                character_name: "Bart".into(),
+                info_type: InfoType::STATS,
+                data_size: 0,
+                data: None,
+            }
+        );
+        assert_eq!(
+            round_trip("RESPONSE Bart HP 34/42"),
+            Message {
+                message_type: MessageType::RESPONSE,
+                character_name: "Bart".into(),
+                info_type: InfoType::HP,
+                data_size: 2,
+                data: Some(CharacterData::HP(HealthPoints {
+                    current: 34,
+                    max: 42
+                })),
+            }
+        );
+    }
+
+    #[test]
+    fn every_data_type_round_trips() {
+        for text in [
+            "RESPONSE Bart STATS 16 12 14 10 10 8",
+            "RESPONSE Bart AGE 30",
+            "RESPONSE Bart CLASS FIGHTER",
+            "RESPONSE Bart CLASS 15",
+            "RESPONSE Bart RACE HALFELF",
+            "RESPONSE Bart LEVEL 5",
+            "RESPONSE Bart SPELLSLOTS 1:1/4 2:0/2 pact:3:1/2",
+            "RESPONSE Bart SPELLS",
+            "WRITE Bart SPELLS Fire Bolt, Magic Missile",
+            "WRITE Bart SPELLS Snilloc\\, Snowball Swarm, Back\\\\slash",
+            "WRITE Bart CASTSPELL 2",
+            "WRITE Bart REST LONG",
+            "RESPONSE Bart CONDITIONS POISONED|PRONE 1",
+            "WRITE Bart REMOVECONDITIONS NONE 1",
+            "RESPONSE Bart SKILLS PERCEPTION|STEALTH STEALTH",
+            "RESPONSE Bart COMBAT 18 -1 25",
+            "WRITE Bart AWARDXP 300",
+            "WRITE Bart ROLL 4d6kh3",
+            "RESPONSE Bart ROLLRESULT 4d6kh3 => 13 +0 d6:3 d6:1x d6:6 d6:4",
+            "RESPONSE SESSION ROLLLOG Bart: 1d20+5 => 17 +5 d20:12; Goblin: 1d6-1 => 0 -1 d6:1",
+            "RESPONSE Bart 200 0a0b",
+        ] {
+            let message = round_trip(text);
+            let encoded = message.encode_jdcp();
+            assert_eq!(
+                crate::decode_jdcp(&encoded),
+                Ok((&b""[..], message)),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn spell_names_escape_commas() {
+        let spells = vec![
+            "Snilloc, Snowball Swarm".to_string(),
+            "Back\\slash".to_string(),
+            "Shield".to_string(),
+        ];
+        let message = Message {
+            message_type: MessageType::WRITE,
+            character_name: "Bart".into(),
+            info_type: InfoType::SPELLS,
+            data_size: CharacterData::SPELLS(spells.clone()).size().unwrap(),
+            data: Some(CharacterData::SPELLS(spells)),
+        };
+        let text = message.to_string();
+        assert_eq!(
+            text,
+            "WRITE Bart SPELLS Snilloc\\, Snowball Swarm, Back\\\\slash, Shield"
+        );
+        assert_eq!(text.parse::<Message>(), Ok(message));
+    }
+
+    #[test]
+    fn rolls_without_a_text_form_are_not_decoded() {
+        let write = |info_type, data: CharacterData| Message {
+            message_type: MessageType::WRITE,
+            character_name: "Bart".into(),
+            info_type,
+            data_size: data.size().unwrap(),
+            data: Some(data),
+        };
+        let empty = write(InfoType::ROLL, CharacterData::ROLL(String::new()));
+        assert_eq!(empty.to_string(), "WRITE Bart ROLL");
+        assert!("WRITE Bart ROLL".parse::<Message>().is_err());
+        assert!(crate::decode_jdcp(&empty.encode_jdcp()).is_err());
+
+        let arrow = write(
+            InfoType::ROLLRESULT,
+            CharacterData::ROLLRESULT(RollRecord {
+                expression: "1d6 => 6".to_string(),
+                result: RollResult {
+                    dice: vec![DieResult {
+                        sides: 6,
+                        value: 2,
+                        kept: true,
+                    }],
+                    modifier: 0,
+                    total: 2,
+                },
+            }),
+        );
+        assert!(arrow.to_string().parse::<Message>().is_err());
+        assert!(crate::decode_jdcp(&arrow.encode_jdcp()).is_err());
+    }
+
+    #[test]
+    fn parse_errors_say_what_was_expected() {
+        let error = "RESPONSE Bart HP 34".parse::<Message>().unwrap_err();
+        assert!(error.to_string().contains("in hp"), "{}", error);
+        let error = "RESPOND Bart HP".parse::<Message>().unwrap_err();
+        assert!(error.to_string().contains("in message type"), "{}", error);
+        let error = "REQUEST Bart HP 34/42".parse::<Message>().unwrap_err();
+        assert!(error.to_string().contains("at line 1"), "{}", error);
+    }
+}
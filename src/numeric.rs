@@ -0,0 +1,243 @@
+//! Serde helpers for writing the wire enums as their discriminant bytes instead of their names,
+//! either for one field with `#[serde(with = "jdcp::numeric")]`:
+//!
+//! ```
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct LogLine {
+//!     #[serde(with = "jdcp::numeric")]
+//!     class: jdcp::character::character_data::class_type::ClassType,
+//! }
+//! ```
+//!
+//! or for everything inside a value wrapped in [`Numeric`]:
+//!
+//! ```
+//! use jdcp::{message::Message, numeric::Numeric};
+//!
+//! let message: Message = "REQUEST Bart HP".parse().unwrap();
+//! let json = serde_json::to_string(&Numeric(&message)).unwrap();
+//! assert!(json.starts_with(r#"{"message_type":170,"#));
+//! ```
+
+use std::cell::Cell;
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    character::character_data::{class_type::ClassType, race_kind::RaceKind, rest_kind::RestKind},
+    message::{InfoType, MessageType},
+};
+
+/// An enum sent on the wire as a single byte.
+pub trait Discriminant: Sized {
+    fn to_discriminant(&self) -> u8;
+    /// `None` when no variant uses `value`.
+    fn from_discriminant(value: u8) -> Option<Self>;
+}
+
+impl Discriminant for MessageType {
+    fn to_discriminant(&self) -> u8 {
+        self.discriminant()
+    }
+
+    fn from_discriminant(value: u8) -> Option<Self> {
+        MessageType::try_from(value).ok()
+    }
+}
+
+/// Bytes no info type uses read back as [`InfoType::Unknown`].
+impl Discriminant for InfoType {
+    fn to_discriminant(&self) -> u8 {
+        self.discriminant()
+    }
+
+    fn from_discriminant(value: u8) -> Option<Self> {
+        Some(InfoType::try_from(value).unwrap_or(InfoType::Unknown(value)))
+    }
+}
+
+impl Discriminant for ClassType {
+    fn to_discriminant(&self) -> u8 {
+        self.discriminant()
+    }
+
+    fn from_discriminant(value: u8) -> Option<Self> {
+        Some(ClassType::from(&[value][..]))
+    }
+}
+
+impl Discriminant for RaceKind {
+    fn to_discriminant(&self) -> u8 {
+        self.discriminant()
+    }
+
+    fn from_discriminant(value: u8) -> Option<Self> {
+        Some(RaceKind::from(&[value][..]))
+    }
+}
+
+impl Discriminant for RestKind {
+    fn to_discriminant(&self) -> u8 {
+        self.discriminant()
+    }
+
+    fn from_discriminant(value: u8) -> Option<Self> {
+        RestKind::try_from(value).ok()
+    }
+}
+
+thread_local! {
+    static NUMERIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Writes and reads every wire enum inside `T` as its discriminant byte.
+#[derive(Debug, PartialEq)]
+pub struct Numeric<T>(pub T);
+
+/// Keeps this thread's wire enums numeric until dropped.
+struct NumericScope(bool);
+
+impl NumericScope {
+    fn enter() -> Self {
+        NumericScope(NUMERIC.replace(true))
+    }
+}
+
+impl Drop for NumericScope {
+    fn drop(&mut self) {
+        NUMERIC.set(self.0);
+    }
+}
+
+impl<T: Serialize> Serialize for Numeric<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let _scope = NumericScope::enter();
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Numeric<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let _scope = NumericScope::enter();
+        T::deserialize(deserializer).map(Numeric)
+    }
+}
+
+/// By name, using the impls `#[serde(remote = "Self")]` derives, or by discriminant inside a
+/// [`Numeric`].
+macro_rules! discriminant_serde {
+    ($($enum:ident),+) => {$(
+        impl Serialize for $enum {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if NUMERIC.get() {
+                    serialize(self, serializer)
+                } else {
+                    $enum::serialize(self, serializer)
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $enum {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                if NUMERIC.get() {
+                    deserialize(deserializer)
+                } else {
+                    $enum::deserialize(deserializer)
+                }
+            }
+        }
+    )+};
+}
+
+discriminant_serde!(MessageType, InfoType, ClassType, RaceKind, RestKind);
+
+pub fn serialize<T: Discriminant, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u8(value.to_discriminant())
+}
+
+pub fn deserialize<'de, T: Discriminant, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let value = u8::deserialize(deserializer)?;
+    T::from_discriminant(value)
+        .ok_or_else(|| D::Error::custom(format!("{} is not a known discriminant", value)))
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_numeric_tests {
+    use super::*;
+    use crate::{
+        character::{
+            character_data::{health_points::HealthPoints, stat_block::StatBlock},
+            Character,
+        },
+        message::Message,
+    };
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Numbered {
+        #[serde(with = "crate::numeric")]
+        message_type: MessageType,
+        #[serde(with = "crate::numeric")]
+        info_type: InfoType,
+        #[serde(with = "crate::numeric")]
+        class: ClassType,
+    }
+
+    #[test]
+    fn enums_serialize_as_discriminants() {
+        let numbered = Numbered {
+            message_type: MessageType::RESPONSE,
+            info_type: InfoType::HP,
+            class: ClassType::Unknown(15),
+        };
+        let json = serde_json::to_string(&numbered).unwrap();
+        assert_eq!(json, r#"{"message_type":187,"info_type":6,"class":15}"#);
+        assert_eq!(serde_json::from_str::<Numbered>(&json).unwrap(), numbered);
+        assert!(
+            serde_json::from_str::<Numbered>(r#"{"message_type":1,"info_type":6,"class":15}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn wrapped_values_serialize_every_enum_as_discriminants() {
+        let message: Message = "RESPONSE Bart CLASS FIGHTER".parse().unwrap();
+        let json = serde_json::to_string(&Numeric(&message)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"message_type":187,"character_name":"Bart","info_type":3,"data_size":1,"data":{"CLASS":7}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Numeric<Message>>(&json).unwrap(),
+            Numeric(message)
+        );
+
+        let character = Character::new(
+            "Bart".to_string(),
+            RaceKind::Unknown(10),
+            ClassType::WIZARD,
+            3,
+            StatBlock::new(8, 14, 12, 16, 10, 10),
+            HealthPoints {
+                current: 14,
+                max: 14,
+            },
+            30,
+            "".to_string(),
+        );
+        let json = serde_json::to_string(&Numeric(&character)).unwrap();
+        assert!(json.contains(r#""race":10,"class":14,"#));
+        assert_eq!(
+            serde_json::from_str::<Numeric<Character>>(&json).unwrap(),
+            Numeric(character)
+        );
+        assert_eq!(
+            serde_json::to_string(&ClassType::WIZARD).unwrap(),
+            r#""WIZARD""#
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::{
+    decode_jdcp_with,
+    dice::{DiceRng, SeededRng},
+    message::DecodeMode,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Direction {
+    CLIENTTOSERVER,
+    SERVERTOCLIENT,
+}
+
+/// One frame seen by the proxy. Connections are numbered in the order clients connected.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecordedFrame {
+    pub elapsed: Duration,
+    pub connection: usize,
+    pub direction: Direction,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseRecordingError(String);
+
+impl fmt::Display for ParseRecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseRecordingError {}
+
+/// Text form of a frame's message, or `undecodable`. Info types this crate doesn't know are shown
+/// as their number and payload hex.
+pub fn frame_text(bytes: &[u8]) -> String {
+    decode_jdcp_with(bytes, DecodeMode::LENIENT).map_or_else(
+        |_| "undecodable".to_string(),
+        |(_, message)| message.to_string(),
+    )
+}
+
+/// A session file line: `seconds connection direction hex text`, e.g.
+/// `0.000300 0 > 6a6463702daa4261727400060000 REQUEST Bart HP`. The text is for people reading the
+/// file; only the hex is read back.
+impl fmt::Display for RecordedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:06} {} {} {} {}",
+            self.elapsed.as_secs(),
+            self.elapsed.subsec_micros(),
+            self.connection,
+            match self.direction {
+                Direction::CLIENTTOSERVER => ">",
+                Direction::SERVERTOCLIENT => "<",
+            },
+            hex::encode(&self.bytes),
+            frame_text(&self.bytes)
+        )
+    }
+}
+
+/// `seconds.micros`, read exactly rather than through a float.
+fn parse_elapsed(elapsed: &str) -> Option<Duration> {
+    let (seconds, fraction) = elapsed.split_once('.').unwrap_or((elapsed, "0"));
+    if fraction.is_empty() || fraction.len() > 6 {
+        return None;
+    }
+    let micros: u32 = format!("{:0<6}", fraction).parse().ok()?;
+    Some(Duration::from_secs(seconds.parse().ok()?) + Duration::from_micros(micros.into()))
+}
+
+impl FromStr for RecordedFrame {
+    type Err = ParseRecordingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.splitn(5, ' ').collect();
+        let [elapsed, connection, direction, bytes, ..] = fields[..] else {
+            return Err(ParseRecordingError(format!(
+                "expected `seconds connection direction hex`, got {:?}",
+                s
+            )));
+        };
+        Ok(RecordedFrame {
+            elapsed: parse_elapsed(elapsed)
+                .ok_or_else(|| ParseRecordingError(format!("invalid time {:?}", elapsed)))?,
+            connection: connection
+                .parse()
+                .map_err(|_| ParseRecordingError(format!("invalid connection {:?}", connection)))?,
+            direction: match direction {
+                ">" => Direction::CLIENTTOSERVER,
+                "<" => Direction::SERVERTOCLIENT,
+                _ => {
+                    return Err(ParseRecordingError(format!(
+                        "invalid direction {:?}, expected > or <",
+                        direction
+                    )))
+                }
+            },
+            bytes: hex::decode(bytes)
+                .map_err(|e| ParseRecordingError(format!("invalid hex: {}", e)))?,
+        })
+    }
+}
+
+/// Reads a session file, skipping blank lines. Frames have to be in the order they were seen.
+pub fn parse_recording(recording: &str) -> Result<Vec<RecordedFrame>, ParseRecordingError> {
+    let mut frames: Vec<RecordedFrame> = Vec::new();
+    for (number, line) in recording.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame: RecordedFrame = line
+            .parse()
+            .map_err(|e| ParseRecordingError(format!("line {}: {}", number + 1, e)))?;
+        if let Some(previous) = frames.last() {
+            if frame.elapsed < previous.elapsed {
+                return Err(ParseRecordingError(format!(
+                    "line {}: {:?} is earlier than the frame before it",
+                    number + 1,
+                    frame.elapsed
+                )));
+            }
+        }
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// A server frame that differed between the recording and a replay. `index` counts the frames
+/// sent to that connection.
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub connection: usize,
+    pub index: usize,
+    pub expected: Option<Vec<u8>>,
+    pub actual: Option<Vec<u8>>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text =
+            |bytes: &Option<Vec<u8>>| bytes.as_deref().map_or("nothing".to_string(), frame_text);
+        write!(
+            f,
+            "connection {} frame {}: expected {}, got {}",
+            self.connection,
+            self.index,
+            text(&self.expected),
+            text(&self.actual)
+        )
+    }
+}
+
+/// Compares the server frames of a recording with those a replay got back, per connection and in
+/// order. Frames to different connections may interleave differently.
+pub fn diff(recording: &[RecordedFrame], actual: &[(usize, Vec<u8>)]) -> Vec<Mismatch> {
+    let expected: Vec<(usize, Vec<u8>)> = recording
+        .iter()
+        .filter(|frame| frame.direction == Direction::SERVERTOCLIENT)
+        .map(|frame| (frame.connection, frame.bytes.clone()))
+        .collect();
+    let mut connections: Vec<usize> = expected
+        .iter()
+        .chain(actual)
+        .map(|(connection, _)| *connection)
+        .collect();
+    connections.sort_unstable();
+    connections.dedup();
+    let for_connection = |frames: &[(usize, Vec<u8>)], connection: usize| -> Vec<Vec<u8>> {
+        frames
+            .iter()
+            .filter(|(to, _)| *to == connection)
+            .map(|(_, bytes)| bytes.clone())
+            .collect()
+    };
+    let mut mismatches = Vec::new();
+    for connection in connections {
+        let expected = for_connection(&expected, connection);
+        let actual = for_connection(actual, connection);
+        for index in 0..expected.len().max(actual.len()) {
+            if expected.get(index) != actual.get(index) {
+                mismatches.push(Mismatch {
+                    connection,
+                    index,
+                    expected: expected.get(index).cloned(),
+                    actual: actual.get(index).cloned(),
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Fault {
+    SPLIT,
+    COALESCE,
+    DELAY,
+    DROP,
+    CORRUPT,
+    RESET,
+}
+
+impl Fault {
+    pub const ALL: [Fault; 6] = [
+        Fault::SPLIT,
+        Fault::COALESCE,
+        Fault::DELAY,
+        Fault::DROP,
+        Fault::CORRUPT,
+        Fault::RESET,
+    ];
+
+    /// Name in a fault spec.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Fault::SPLIT => "split",
+            Fault::COALESCE => "coalesce",
+            Fault::DELAY => "delay",
+            Fault::DROP => "drop",
+            Fault::CORRUPT => "corrupt",
+            Fault::RESET => "reset",
+        }
+    }
+}
+
+/// Percent chance of each fault hitting a frame, and the seed every random choice comes from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Faults {
+    pub seed: u64,
+    pub chances: [u8; 6],
+    pub max_delay: Duration,
+}
+
+impl Faults {
+    pub fn chance(&self, fault: Fault) -> u8 {
+        self.chances[fault as usize]
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseFaultsError(String);
+
+impl fmt::Display for ParseFaultsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseFaultsError {}
+
+/// A fault spec, e.g. `split=30,delay=10,max-delay=250,corrupt=1,seed=42`. Chances are percent,
+/// `max-delay` is in milliseconds and defaults to 100.
+impl fmt::Display for Faults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for fault in Fault::ALL {
+            if self.chance(fault) > 0 {
+                write!(f, "{}={},", fault.name(), self.chance(fault))?;
+            }
+        }
+        write!(
+            f,
+            "max-delay={},seed={}",
+            self.max_delay.as_millis(),
+            self.seed
+        )
+    }
+}
+
+/// Without a `seed` one is picked at random; print the parsed spec to be able to repeat the run.
+impl FromStr for Faults {
+    type Err = ParseFaultsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut faults = Faults {
+            seed: SeededRng::from_entropy().next_u64(),
+            chances: [0; 6],
+            max_delay: Duration::from_millis(100),
+        };
+        for setting in s.split(',').filter(|setting| !setting.is_empty()) {
+            let (name, value) = setting.split_once('=').ok_or_else(|| {
+                ParseFaultsError(format!("expected name=value, got {:?}", setting))
+            })?;
+            let invalid = || ParseFaultsError(format!("invalid value for {}: {:?}", name, value));
+            match name {
+                "seed" => faults.seed = value.parse().map_err(|_| invalid())?,
+                "max-delay" => {
+                    faults.max_delay = Duration::from_millis(value.parse().map_err(|_| invalid())?)
+                }
+                _ => {
+                    let fault = Fault::ALL
+                        .into_iter()
+                        .find(|fault| fault.name() == name)
+                        .ok_or_else(|| ParseFaultsError(format!("unknown fault {:?}", name)))?;
+                    faults.chances[fault as usize] = value
+                        .parse()
+                        .ok()
+                        .filter(|chance| *chance <= 100)
+                        .ok_or_else(invalid)?;
+                }
+            }
+        }
+        Ok(faults)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FaultAction {
+    WRITE(Vec<u8>),
+    WAIT(Duration),
+    RESET,
+}
+
+/// Gap between the writes of a split frame, so they leave as separate segments.
+pub const SPLIT_GAP: Duration = Duration::from_millis(5);
+
+/// Decides what happens to each frame of one stream. Every fault draws from its own generator,
+/// so turning one fault on or off doesn't change what the others do.
+#[derive(Debug)]
+pub struct FaultInjector {
+    faults: Faults,
+    rngs: Vec<SeededRng>,
+    held: Vec<u8>,
+}
+
+impl FaultInjector {
+    /// `stream` tells apart the injectors of one run, e.g. one per connection and direction.
+    pub fn new(faults: &Faults, stream: u64) -> Self {
+        FaultInjector {
+            faults: faults.clone(),
+            rngs: Fault::ALL
+                .iter()
+                .map(|fault| {
+                    let mut seeder = SeededRng::new(faults.seed ^ (stream << 8 | *fault as u64));
+                    SeededRng::new(seeder.next_u64())
+                })
+                .collect(),
+            held: Vec::new(),
+        }
+    }
+
+    fn hits(&mut self, fault: Fault) -> bool {
+        let chance = self.faults.chance(fault);
+        chance > 0 && self.rngs[fault as usize].roll_die(100) <= u32::from(chance)
+    }
+
+    fn pick(&mut self, fault: Fault, count: u32) -> u32 {
+        self.rngs[fault as usize].roll_die(count)
+    }
+
+    /// What to send for `frame`, and the faults that hit it.
+    pub fn apply(&mut self, frame: &[u8]) -> (Vec<FaultAction>, Vec<Fault>) {
+        if self.hits(Fault::RESET) {
+            return (vec![FaultAction::RESET], vec![Fault::RESET]);
+        }
+        if self.hits(Fault::DROP) {
+            return (Vec::new(), vec![Fault::DROP]);
+        }
+        let mut hit = Vec::new();
+        let mut bytes = frame.to_vec();
+        if !bytes.is_empty() && self.hits(Fault::CORRUPT) {
+            let at = self.pick(Fault::CORRUPT, bytes.len() as u32) as usize - 1;
+            bytes[at] ^= self.pick(Fault::CORRUPT, 255) as u8;
+            hit.push(Fault::CORRUPT);
+        }
+        self.held.extend(bytes);
+        if self.hits(Fault::COALESCE) {
+            hit.push(Fault::COALESCE);
+            return (Vec::new(), hit);
+        }
+        let bytes = std::mem::take(&mut self.held);
+        let mut actions = Vec::new();
+        if self.hits(Fault::DELAY) {
+            let max_delay = self.faults.max_delay.as_millis().clamp(1, u32::MAX.into()) as u32;
+            let delay = self.pick(Fault::DELAY, max_delay);
+            actions.push(FaultAction::WAIT(Duration::from_millis(delay.into())));
+            hit.push(Fault::DELAY);
+        }
+        if bytes.len() > 1 && self.hits(Fault::SPLIT) {
+            let at = self.pick(Fault::SPLIT, bytes.len() as u32 - 1) as usize;
+            actions.push(FaultAction::WRITE(bytes[..at].to_vec()));
+            actions.push(FaultAction::WAIT(SPLIT_GAP));
+            actions.push(FaultAction::WRITE(bytes[at..].to_vec()));
+            hit.push(Fault::SPLIT);
+        } else {
+            actions.push(FaultAction::WRITE(bytes));
+        }
+        (actions, hit)
+    }
+
+    /// Whether coalesced frames are waiting for the next one.
+    pub fn holding(&self) -> bool {
+        !self.held.is_empty()
+    }
+
+    /// Sends coalesced frames without waiting for another, e.g. when the stream goes quiet.
+    pub fn flush(&mut self) -> Vec<FaultAction> {
+        if self.held.is_empty() {
+            Vec::new()
+        } else {
+            vec![FaultAction::WRITE(std::mem::take(&mut self.held))]
+        }
+    }
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_proxy_tests {
+    use super::*;
+
+    const SESSION: &str = "0.000100 0 > 6a6463702daa4261727400060000 REQUEST Bart HP
+0.000250 0 < 6a6463702dbb42617274000602001e2a RESPONSE Bart HP 30/42
+
+0.001000 1 > 6a6463702dcc4261727400060200222a WRITE Bart HP 34/42
+0.001200 1 < 6a6463702dbb4261727400060200222a RESPONSE Bart HP 34/42
+0.001300 0 < 6a6463702dbb4261727400060200222a RESPONSE Bart HP 34/42
+";
+
+    #[test]
+    fn session_files_round_trip() {
+        let frames = parse_recording(SESSION).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[2].elapsed, Duration::from_millis(1));
+        assert_eq!(frames[2].direction, Direction::CLIENTTOSERVER);
+        let written: Vec<String> = frames.iter().map(RecordedFrame::to_string).collect();
+        assert_eq!(written.join("\n"), SESSION.replace("\n\n", "\n").trim_end());
+        assert_eq!(
+            parse_recording("0.1 0 > 6a6\n"),
+            Err(ParseRecordingError(
+                "line 1: invalid hex: Odd number of digits".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_recording("0.2 0 > 6a\n\n0.1 0 > 6a\n"),
+            Err(ParseRecordingError(
+                "line 3: 100ms is earlier than the frame before it".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn replies_are_diffed_per_connection() {
+        let frames = parse_recording(SESSION).unwrap();
+        let reply = |frame: &RecordedFrame| (frame.connection, frame.bytes.clone());
+        let reordered = vec![reply(&frames[1]), reply(&frames[4]), reply(&frames[3])];
+        assert_eq!(diff(&frames, &reordered), vec![]);
+
+        let missing = vec![reply(&frames[1]), reply(&frames[3])];
+        assert_eq!(
+            diff(&frames, &missing)
+                .iter()
+                .map(Mismatch::to_string)
+                .collect::<Vec<_>>(),
+            vec!["connection 0 frame 1: expected RESPONSE Bart HP 34/42, got nothing"]
+        );
+    }
+
+    const REQUEST: &[u8] = b"jdcp-\xAABart\x00\x06\x00\x00";
+
+    fn written(actions: &[FaultAction]) -> Vec<u8> {
+        actions
+            .iter()
+            .flat_map(|action| match action {
+                FaultAction::WRITE(bytes) => bytes.clone(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fault_specs_round_trip() {
+        let faults: Faults = "split=30,corrupt=1,max-delay=250,seed=42".parse().unwrap();
+        assert_eq!(faults.chance(Fault::SPLIT), 30);
+        assert_eq!(faults.chance(Fault::DROP), 0);
+        assert_eq!(
+            faults.to_string(),
+            "split=30,corrupt=1,max-delay=250,seed=42"
+        );
+        assert_eq!(faults.to_string().parse(), Ok(faults));
+        assert_eq!(
+            "split=101".parse::<Faults>(),
+            Err(ParseFaultsError(
+                "invalid value for split: \"101\"".to_string()
+            ))
+        );
+        assert_eq!(
+            "jitter=5".parse::<Faults>(),
+            Err(ParseFaultsError("unknown fault \"jitter\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn faults_are_reproducible() {
+        let faults: Faults = "split=50,coalesce=20,delay=30,drop=10,corrupt=10,seed=7"
+            .parse()
+            .unwrap();
+        let run = || {
+            let mut injector = FaultInjector::new(&faults, 3);
+            (0..50).map(|_| injector.apply(REQUEST)).collect::<Vec<_>>()
+        };
+        let first = run();
+        assert_eq!(first, run());
+        for fault in Fault::ALL
+            .into_iter()
+            .filter(|fault| *fault != Fault::RESET)
+        {
+            assert!(
+                first.iter().any(|(_, hit)| hit.contains(&fault)),
+                "{:?}",
+                fault
+            );
+        }
+
+        // Turning another fault on leaves the decisions of the rest alone.
+        let mut split_only = FaultInjector::new(&"split=50,seed=7".parse().unwrap(), 3);
+        let mut split_and_delay =
+            FaultInjector::new(&"split=50,delay=100,seed=7".parse().unwrap(), 3);
+        for _ in 0..20 {
+            let split = split_only.apply(REQUEST).1.contains(&Fault::SPLIT);
+            assert_eq!(
+                split_and_delay.apply(REQUEST).1.contains(&Fault::SPLIT),
+                split
+            );
+        }
+    }
+
+    #[test]
+    fn each_fault_does_what_it_says() {
+        let mut split = FaultInjector::new(&"split=100,seed=1".parse().unwrap(), 0);
+        let (actions, _) = split.apply(REQUEST);
+        assert_eq!(actions.len(), 3);
+        assert_eq!(actions[1], FaultAction::WAIT(SPLIT_GAP));
+        assert_eq!(written(&actions), REQUEST);
+
+        let mut coalesce = FaultInjector::new(&"coalesce=100,seed=1".parse().unwrap(), 0);
+        assert_eq!(coalesce.apply(REQUEST).0, vec![]);
+        assert_eq!(coalesce.apply(REQUEST).0, vec![]);
+        assert!(coalesce.holding());
+        assert_eq!(written(&coalesce.flush()), [REQUEST, REQUEST].concat());
+
+        let mut corrupt = FaultInjector::new(&"corrupt=100,seed=1".parse().unwrap(), 0);
+        let corrupted = written(&corrupt.apply(REQUEST).0);
+        assert_eq!(
+            corrupted
+                .iter()
+                .zip(REQUEST)
+                .filter(|(a, b)| a != b)
+                .count(),
+            1
+        );
+
+        let mut drop = FaultInjector::new(&"drop=100,seed=1".parse().unwrap(), 0);
+        assert_eq!(drop.apply(REQUEST), (vec![], vec![Fault::DROP]));
+        let mut reset = FaultInjector::new(&"reset=100,seed=1".parse().unwrap(), 0);
+        assert_eq!(reset.apply(REQUEST).0, vec![FaultAction::RESET]);
+        let mut delay = FaultInjector::new(&"delay=100,max-delay=20,seed=1".parse().unwrap(), 0);
+        match delay.apply(REQUEST).0[..] {
+            [FaultAction::WAIT(wait), FaultAction::WRITE(_)] => {
+                assert!(wait <= Duration::from_millis(20))
+            }
+            ref actions => panic!("{:?}", actions),
+        }
+    }
+}
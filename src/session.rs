@@ -0,0 +1,697 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    character::{
+        ability_scores::{self, AbilityScoreMethod, ValidationReport, ROLLED_SCORE_EXPRESSION},
+        character_data::stat_block::Ability,
+        character_data::{
+            roll_record::{RollLogEntry, RollRecord},
+            CharacterData,
+        },
+        Character, CharacterError,
+    },
+    dice::{self, DiceError, SeededRng},
+    message::{InfoType, Message, MessageType},
+};
+
+pub type ClientId = usize;
+
+/// Character name to `REQUEST` a `ROLLLOG` with to get every roll made in the session.
+pub const SESSION_LOG: &str = "SESSION";
+
+#[derive(Debug, PartialEq)]
+pub enum SessionError {
+    UnknownCharacter(String),
+    NotReadable(InfoType),
+    UnexpectedMessage(MessageType),
+    Character(CharacterError),
+    Dice(DiceError),
+    InvalidAbilityScores(ValidationReport),
+    /// The reply's data is more than a data size can hold.
+    TooLarge(InfoType),
+    /// Characters can not be named [`SESSION_LOG`].
+    ReservedName(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::UnknownCharacter(name) => write!(f, "No character named {}", name),
+            SessionError::NotReadable(info_type) => write!(f, "{:?} can not be read", info_type),
+            SessionError::UnexpectedMessage(message_type) => {
+                write!(f, "Clients can not send {:?} messages", message_type)
+            }
+            SessionError::Character(e) => write!(f, "{}", e),
+            SessionError::Dice(e) => write!(f, "{}", e),
+            SessionError::InvalidAbilityScores(report) => write!(f, "{}", report),
+            SessionError::TooLarge(info_type) => {
+                write!(f, "{:?} is too large for one message", info_type)
+            }
+            SessionError::ReservedName(name) => write!(f, "{} is not a character name", name),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<CharacterError> for SessionError {
+    fn from(value: CharacterError) -> Self {
+        SessionError::Character(value)
+    }
+}
+
+impl From<DiceError> for SessionError {
+    fn from(value: DiceError) -> Self {
+        SessionError::Dice(value)
+    }
+}
+
+/// An encoded message the server should send to `client`.
+#[derive(Debug, PartialEq)]
+pub struct Outgoing {
+    pub client: ClientId,
+    pub bytes: Vec<u8>,
+}
+
+/// Server side state: the characters being played, which clients follow which info types and
+/// every roll the server has made.
+#[derive(Debug)]
+pub struct Session {
+    characters: HashMap<String, Character>,
+    subscriptions: HashMap<String, Vec<(ClientId, InfoType)>>,
+    rng: SeededRng,
+    roll_log: Vec<RollLogEntry>,
+    /// How many [`ROLLED_SCORE_EXPRESSION`] rolls per name went into characters already made.
+    spent_score_rolls: HashMap<String, usize>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::with_rng(SeededRng::from_entropy())
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    /// A session whose rolls are reproducible from `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Session::with_rng(SeededRng::new(seed))
+    }
+
+    fn with_rng(rng: SeededRng) -> Self {
+        Session {
+            characters: HashMap::new(),
+            subscriptions: HashMap::new(),
+            rng,
+            roll_log: Vec::new(),
+            spent_score_rolls: HashMap::new(),
+        }
+    }
+
+    /// Adds or replaces a character, refusing the name [`SESSION_LOG`].
+    pub fn insert_character(&mut self, character: Character) -> Result<(), SessionError> {
+        if character.name == SESSION_LOG {
+            return Err(SessionError::ReservedName(character.name));
+        }
+        self.characters.insert(character.name.clone(), character);
+        Ok(())
+    }
+
+    pub fn character(&self, name: &str) -> Option<&Character> {
+        self.characters.get(name)
+    }
+
+    /// Accepts a new character whose `stats` are its base ability scores, refusing them unless
+    /// they were made by `method`. Rolled scores have to match the first six
+    /// [`ROLLED_SCORE_EXPRESSION`] rolls the session logged for the character's name, which are
+    /// then spent, so rerolling doesn't help. Racial increases, with `choices` for half-elves, are
+    /// added before the character is inserted.
+    pub fn create_character(
+        &mut self,
+        mut character: Character,
+        method: AbilityScoreMethod,
+        choices: &[Ability],
+    ) -> Result<(), SessionError> {
+        let spent = self
+            .spent_score_rolls
+            .get(&character.name)
+            .copied()
+            .unwrap_or(0);
+        let rolls: Vec<u8> = self
+            .roll_log
+            .iter()
+            .filter(|entry| {
+                entry.roller == character.name && entry.record.expression == ROLLED_SCORE_EXPRESSION
+            })
+            .skip(spent)
+            .take(6)
+            .map(|entry| entry.record.result.total as u8)
+            .collect();
+        let report = ability_scores::validate(&character.stats, method, &rolls);
+        if !report.is_valid() {
+            return Err(SessionError::InvalidAbilityScores(report));
+        }
+        let name = character.name.clone();
+        character.stats =
+            ability_scores::apply_racial_increases(&character.stats, &character.race, choices);
+        self.insert_character(character)?;
+        if method == AbilityScoreMethod::ROLLED {
+            self.spent_score_rolls.insert(name, spent + rolls.len());
+        }
+        Ok(())
+    }
+
+    /// Every roll made so far, oldest first.
+    pub fn roll_log(&self) -> &[RollLogEntry] {
+        &self.roll_log
+    }
+
+    /// Rolls `expression` for `roller` and appends it to the log. The roller does not have to be
+    /// a known character, so a DM can roll for monsters or the table.
+    pub fn roll(&mut self, roller: &str, expression: &str) -> Result<RollRecord, SessionError> {
+        let result = dice::roll(expression, &mut self.rng)?;
+        let record = RollRecord {
+            expression: expression.to_string(),
+            result,
+        };
+        self.roll_log.push(RollLogEntry {
+            roller: roller.to_string(),
+            record: record.clone(),
+        });
+        Ok(record)
+    }
+
+    /// The newest log entries for `roller` (or all of them for [`SESSION_LOG`]) that fit in one
+    /// message.
+    fn roll_log_for(&self, roller: &str) -> Vec<RollLogEntry> {
+        let mut size = 0u16;
+        let mut entries: Vec<RollLogEntry> = self
+            .roll_log
+            .iter()
+            .rev()
+            .filter(|entry| roller == SESSION_LOG || entry.roller == roller)
+            .take_while(|entry| {
+                let total = entry
+                    .size()
+                    .and_then(|entry_size| size.checked_add(entry_size));
+                if let Some(total) = total {
+                    size = total;
+                }
+                total.is_some()
+            })
+            .cloned()
+            .collect();
+        entries.reverse();
+        entries
+    }
+
+    /// Handles one message from `client`, returning the replies and any pushes to subscribers.
+    pub fn handle(
+        &mut self,
+        client: ClientId,
+        message: Message,
+    ) -> Result<Vec<Outgoing>, SessionError> {
+        let name: &str = &message.character_name;
+        match (message.message_type, &message.data) {
+            (MessageType::WRITE, Some(CharacterData::ROLL(expression))) => {
+                let record = self.roll(name, expression)?;
+                return Ok(vec![response(
+                    client,
+                    name,
+                    CharacterData::ROLLRESULT(record),
+                )?]);
+            }
+            (MessageType::REQUEST, _) if message.info_type == InfoType::ROLLLOG => {
+                let entries = self.roll_log_for(name);
+                return Ok(vec![response(
+                    client,
+                    name,
+                    CharacterData::ROLLLOG(entries),
+                )?]);
+            }
+            _ => (),
+        }
+        let character = self
+            .characters
+            .get_mut(name)
+            .ok_or_else(|| SessionError::UnknownCharacter(name.to_string()))?;
+
+        match message.message_type {
+            MessageType::REQUEST => {
+                let data = character
+                    .info(&message.info_type)
+                    .ok_or(SessionError::NotReadable(message.info_type))?;
+                Ok(vec![response(client, name, data)?])
+            }
+            MessageType::SUBSCRIBE => {
+                let data = character
+                    .info(&message.info_type)
+                    .ok_or(SessionError::NotReadable(message.info_type))?;
+                let subscribers = self.subscriptions.entry(name.to_string()).or_default();
+                if !subscribers.contains(&(client, message.info_type)) {
+                    subscribers.push((client, message.info_type));
+                }
+                Ok(vec![response(client, name, data)?])
+            }
+            MessageType::WRITE => {
+                let data = message
+                    .data
+                    .ok_or(SessionError::UnexpectedMessage(MessageType::WRITE))?;
+                let readable = message.info_type.readable();
+                let subscribers = self.subscriptions.get(name).cloned().unwrap_or_default();
+                let before: Vec<Option<CharacterData>> = subscribers
+                    .iter()
+                    .map(|(_, info_type)| character.info(info_type))
+                    .collect();
+
+                character.apply(data)?;
+
+                let mut outgoing = Vec::new();
+                if let Some(data) = character.info(&readable) {
+                    outgoing.push(response(client, name, data)?);
+                }
+                for ((subscriber, info_type), before) in subscribers.iter().zip(before) {
+                    if *subscriber == client && *info_type == readable {
+                        continue;
+                    }
+                    match character.info(info_type) {
+                        Some(after) if Some(&after) != before.as_ref() => {
+                            outgoing.push(response(*subscriber, name, after)?)
+                        }
+                        _ => (),
+                    }
+                }
+                Ok(outgoing)
+            }
+            MessageType::RESPONSE => Err(SessionError::UnexpectedMessage(MessageType::RESPONSE)),
+        }
+    }
+
+    /// Drops every subscription held by `client`.
+    pub fn disconnect(&mut self, client: ClientId) {
+        for subscribers in self.subscriptions.values_mut() {
+            subscribers.retain(|(subscriber, _)| *subscriber != client);
+        }
+    }
+}
+
+fn response(client: ClientId, name: &str, data: CharacterData) -> Result<Outgoing, SessionError> {
+    let info_type = data.info_type();
+    Ok(Outgoing {
+        client,
+        bytes: Message {
+            message_type: MessageType::RESPONSE,
+            character_name: name.into(),
+            info_type,
+            data_size: data.size().ok_or(SessionError::TooLarge(info_type))?,
+            data: Some(data),
+        }
+        .encode_jdcp(),
+    })
+}
+
+#[cfg(test)]
+mod josh_dnd_character_protocol_session_tests {
+    use super::*;
+    use crate::character::character_data::{
+        class_type::ClassType,
+        conditions::{ConditionFlags, Conditions},
+        health_points::HealthPoints,
+        race_kind::RaceKind,
+        stat_block::StatBlock,
+    };
+
+    fn session_with_bart() -> Session {
+        let mut session = Session::new();
+        session
+            .insert_character(Character::new(
+                "Bart".to_string(),
+                RaceKind::HUMAN,
+                ClassType::FIGHTER,
+                5,
+                StatBlock::new(16, 12, 14, 10, 10, 8),
+                HealthPoints {
+                    current: 42,
+                    max: 42,
+                },
+                30,
+                "".to_string(),
+            ))
+            .unwrap();
+        session
+    }
+
+    fn message(
+        message_type: MessageType,
+        info_type: InfoType,
+        data: Option<CharacterData>,
+    ) -> Message<'static> {
+        Message {
+            message_type,
+            character_name: "Bart".into(),
+            info_type,
+            data_size: data.as_ref().and_then(CharacterData::size).unwrap_or(0),
+            data,
+        }
+    }
+
+    #[test]
+    fn subscribers_are_pushed_condition_changes() {
+        let mut session = session_with_bart();
+        let subscribed = session
+            .handle(
+                2,
+                message(MessageType::SUBSCRIBE, InfoType::CONDITIONS, None),
+            )
+            .unwrap();
+        assert_eq!(
+            subscribed,
+            vec![response(2, "Bart", CharacterData::CONDITIONS(Conditions::default())).unwrap()]
+        );
+
+        let poisoned = Conditions::new(ConditionFlags::POISONED, 0);
+        let outgoing = session
+            .handle(
+                1,
+                message(
+                    MessageType::WRITE,
+                    InfoType::ADDCONDITIONS,
+                    Some(CharacterData::ADDCONDITIONS(poisoned)),
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            outgoing,
+            vec![
+                response(1, "Bart", CharacterData::CONDITIONS(poisoned)).unwrap(),
+                response(2, "Bart", CharacterData::CONDITIONS(poisoned)).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_values_are_not_pushed() {
+        let mut session = session_with_bart();
+        session
+            .handle(
+                2,
+                message(MessageType::SUBSCRIBE, InfoType::CONDITIONS, None),
+            )
+            .unwrap();
+        let outgoing = session
+            .handle(
+                1,
+                message(
+                    MessageType::WRITE,
+                    InfoType::REMOVECONDITIONS,
+                    Some(CharacterData::REMOVECONDITIONS(Conditions::new(
+                        ConditionFlags::PRONE,
+                        0,
+                    ))),
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            outgoing,
+            vec![response(1, "Bart", CharacterData::CONDITIONS(Conditions::default())).unwrap()]
+        );
+
+        session.disconnect(2);
+        let outgoing = session
+            .handle(
+                1,
+                message(
+                    MessageType::WRITE,
+                    InfoType::ADDCONDITIONS,
+                    Some(CharacterData::ADDCONDITIONS(Conditions::new(
+                        ConditionFlags::PRONE,
+                        0,
+                    ))),
+                ),
+            )
+            .unwrap();
+        assert_eq!(outgoing.len(), 1);
+    }
+
+    #[test]
+    fn unknown_characters_and_write_only_reads_error() {
+        let mut session = session_with_bart();
+        assert_eq!(
+            session.handle(1, message(MessageType::REQUEST, InfoType::CASTSPELL, None)),
+            Err(SessionError::NotReadable(InfoType::CASTSPELL))
+        );
+        let mut request = message(MessageType::REQUEST, InfoType::HP, None);
+        request.character_name = "Lisa".into();
+        assert_eq!(
+            session.handle(1, request),
+            Err(SessionError::UnknownCharacter("Lisa".to_string()))
+        );
+    }
+
+    #[test]
+    fn rolls_are_logged_per_character_and_session() {
+        let mut session = session_with_bart();
+        session.rng = SeededRng::new(7);
+        let outgoing = session
+            .handle(
+                1,
+                message(
+                    MessageType::WRITE,
+                    InfoType::ROLL,
+                    Some(CharacterData::ROLL("2d6+3".to_string())),
+                ),
+            )
+            .unwrap();
+        let record = session.roll_log()[0].record.clone();
+        assert_eq!(record.result.dice.len(), 2);
+        assert_eq!(
+            record.result.total,
+            record
+                .result
+                .dice
+                .iter()
+                .map(|die| die.value as i32)
+                .sum::<i32>()
+                + 3
+        );
+        assert_eq!(
+            outgoing,
+            vec![response(1, "Bart", CharacterData::ROLLRESULT(record)).unwrap()]
+        );
+
+        let mut goblin = message(
+            MessageType::WRITE,
+            InfoType::ROLL,
+            Some(CharacterData::ROLL("1d20".to_string())),
+        );
+        goblin.character_name = "Goblin".into();
+        session.handle(3, goblin).unwrap();
+
+        let bart_log = session
+            .handle(2, message(MessageType::REQUEST, InfoType::ROLLLOG, None))
+            .unwrap();
+        assert_eq!(
+            bart_log,
+            vec![response(
+                2,
+                "Bart",
+                CharacterData::ROLLLOG(session.roll_log()[..1].to_vec())
+            )
+            .unwrap()]
+        );
+        let mut everything = message(MessageType::REQUEST, InfoType::ROLLLOG, None);
+        everything.character_name = SESSION_LOG.into();
+        assert_eq!(
+            session.handle(2, everything).unwrap(),
+            vec![response(
+                2,
+                SESSION_LOG,
+                CharacterData::ROLLLOG(session.roll_log().to_vec())
+            )
+            .unwrap()]
+        );
+    }
+
+    #[test]
+    fn created_characters_must_use_valid_scores() {
+        let mut session = Session::with_seed(11);
+        let lisa = |stats| {
+            Character::new(
+                "Lisa".to_string(),
+                RaceKind::HALFELF,
+                ClassType::BARD,
+                1,
+                stats,
+                HealthPoints { current: 9, max: 9 },
+                21,
+                "".to_string(),
+            )
+        };
+        let cheating = StatBlock::new(18, 18, 18, 18, 18, 18);
+        assert!(matches!(
+            session.create_character(lisa(cheating.clone()), AbilityScoreMethod::ROLLED, &[]),
+            Err(SessionError::InvalidAbilityScores(_))
+        ));
+
+        let totals: Vec<u8> = (0..6)
+            .map(|_| {
+                session
+                    .roll("Lisa", ROLLED_SCORE_EXPRESSION)
+                    .unwrap()
+                    .result
+                    .total as u8
+            })
+            .collect();
+        let mut rolled = [0u8; 6];
+        rolled.copy_from_slice(&totals);
+        let base = ability_scores::assign(rolled, Ability::ALL);
+        let rerolls: Vec<u8> = (0..6)
+            .map(|_| {
+                session
+                    .roll("Lisa", ROLLED_SCORE_EXPRESSION)
+                    .unwrap()
+                    .result
+                    .total as u8
+            })
+            .collect();
+        assert_ne!(rerolls, totals);
+        rolled.copy_from_slice(&rerolls);
+        assert!(matches!(
+            session.create_character(
+                lisa(ability_scores::assign(rolled, Ability::ALL)),
+                AbilityScoreMethod::ROLLED,
+                &[]
+            ),
+            Err(SessionError::InvalidAbilityScores(_))
+        ));
+        session
+            .create_character(
+                lisa(base.clone()),
+                AbilityScoreMethod::ROLLED,
+                &[Ability::DEXTERITY, Ability::WISDOM],
+            )
+            .unwrap();
+        assert_eq!(
+            session.character("Lisa").unwrap().stats,
+            ability_scores::apply_racial_increases(
+                &base,
+                &RaceKind::HALFELF,
+                &[Ability::DEXTERITY, Ability::WISDOM]
+            )
+        );
+        assert!(matches!(
+            session.create_character(lisa(base), AbilityScoreMethod::ROLLED, &[]),
+            Err(SessionError::InvalidAbilityScores(_))
+        ));
+    }
+
+    #[test]
+    fn seeded_sessions_roll_the_same() {
+        let mut first = Session::with_seed(42);
+        let mut second = Session::with_seed(42);
+        assert_eq!(
+            first.roll("Bart", "4d6kh3").unwrap(),
+            second.roll("Bart", "4d6kh3").unwrap()
+        );
+        assert_eq!(
+            first.roll("Bart", "1d2000"),
+            Err(SessionError::Dice(DiceError::TooManySides(2000)))
+        );
+        let padded = format!("1{}", " ".repeat(65534));
+        assert_eq!(
+            first.roll("Bart", &padded),
+            Err(SessionError::Dice(DiceError::TooLong(65535)))
+        );
+        assert_eq!(first.roll_log().len(), 1);
+    }
+
+    #[test]
+    fn awarding_xp_pushes_new_level() {
+        let mut session = session_with_bart();
+        session
+            .handle(2, message(MessageType::SUBSCRIBE, InfoType::LEVEL, None))
+            .unwrap();
+        let outgoing = session
+            .handle(
+                1,
+                message(
+                    MessageType::WRITE,
+                    InfoType::AWARDXP,
+                    Some(CharacterData::AWARDXP(7500)),
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            outgoing,
+            vec![
+                response(1, "Bart", CharacterData::XP(14000)).unwrap(),
+                response(2, "Bart", CharacterData::LEVEL(6)).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn levels_only_change_through_xp() {
+        let mut session = session_with_bart();
+        assert_eq!(
+            session.handle(
+                1,
+                message(
+                    MessageType::WRITE,
+                    InfoType::LEVEL,
+                    Some(CharacterData::LEVEL(20)),
+                ),
+            ),
+            Err(SessionError::Character(CharacterError::ReadOnly(
+                InfoType::LEVEL
+            )))
+        );
+        assert_eq!(session.character("Bart").unwrap().level, 5);
+
+        session
+            .handle(
+                1,
+                message(
+                    MessageType::WRITE,
+                    InfoType::XP,
+                    Some(CharacterData::XP(23000)),
+                ),
+            )
+            .unwrap();
+        let bart = session.character("Bart").unwrap();
+        assert_eq!((bart.level, bart.xp), (7, 23000));
+        assert!(bart.hp.max > 42);
+    }
+
+    #[test]
+    fn session_log_is_not_a_character_name() {
+        let mut session = session_with_bart();
+        let impostor = || {
+            Character::new(
+                SESSION_LOG.to_string(),
+                RaceKind::HUMAN,
+                ClassType::FIGHTER,
+                1,
+                StatBlock::new(15, 14, 13, 12, 10, 8),
+                HealthPoints {
+                    current: 12,
+                    max: 12,
+                },
+                30,
+                "".to_string(),
+            )
+        };
+        assert_eq!(
+            session.insert_character(impostor()),
+            Err(SessionError::ReservedName(SESSION_LOG.to_string()))
+        );
+        assert_eq!(
+            session.create_character(impostor(), AbilityScoreMethod::STANDARDARRAY, &[]),
+            Err(SessionError::ReservedName(SESSION_LOG.to_string()))
+        );
+        assert!(session.character(SESSION_LOG).is_none());
+    }
+}